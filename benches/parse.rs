@@ -0,0 +1,174 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Parsing cost of a large vector set.
+//!
+//! Compares single-pass construction of an `AcvpRequest` with the parser
+//! as it was before, reproduced in `baseline` for hash vector sets: every
+//! test group was dumped and re-parsed, and every test case was dumped and
+//! parsed twice, once by `AcvpTest` and once by the algorithm module. Run
+//! with `cargo bench --bench parse`.
+
+use std::time::{Duration, Instant};
+
+use acvp_parser::{hash::SecureHash, parser::AcvpRequest};
+use json::JsonValue;
+
+const GROUPS: usize = 20;
+const TESTS: usize = 1000;
+const MSG_LEN: usize = 1024;
+const ROUNDS: u32 = 5;
+
+fn vector_set() -> String {
+    let mut groups = JsonValue::new_array();
+    for tgid in 0..GROUPS {
+        let mut tests = JsonValue::new_array();
+        for i in 0..TESTS {
+            let msg = vec![(i % 256) as u8; MSG_LEN];
+            let tc = json::object! {
+                tcId: tgid * TESTS + i + 1,
+                msg: hex::encode(msg).to_ascii_uppercase(),
+                len: MSG_LEN * 8,
+            };
+            tests.push(tc).unwrap();
+        }
+        let tg = json::object! {
+            tgId: tgid + 1,
+            testType: "AFT",
+            tests: tests,
+        };
+        groups.push(tg).unwrap();
+    }
+    let vs = json::object! {
+        vsId: 1,
+        algorithm: "SHA2-256",
+        revision: "1.0",
+        isSample: false,
+        testGroups: groups,
+    };
+    json::array![json::object! { acvVersion: "1.0" }, vs].dump()
+}
+
+/// The pre-single-pass parser, reduced to what it did for a hash vector
+/// set. Each step mirrors the former `AcvpRequest::new`,
+/// `AcvpTestGroup::new`, `AcvpTest::new` and `SecureHash::new`. The fields
+/// are kept as they were, even those the benchmark does not read.
+#[allow(dead_code)]
+mod baseline {
+    use json::JsonValue;
+
+    pub struct SecureHash {
+        pub tcid: u32,
+        pub msg: Vec<u8>,
+    }
+
+    pub struct AcvpTest {
+        pub tcid: u32,
+        pub test: SecureHash,
+        pub test_json: JsonValue,
+    }
+
+    pub struct AcvpTestGroup {
+        pub tgid: u32,
+        pub test_type: String,
+        pub tests: Vec<AcvpTest>,
+        pub testgroup_json: JsonValue,
+    }
+
+    pub struct AcvpRequest {
+        pub vsid: u32,
+        pub algorithm: String,
+        pub testgroups: Vec<AcvpTestGroup>,
+        pub request_json: JsonValue,
+    }
+
+    fn secure_hash(testjson: &str) -> SecureHash {
+        let test = json::parse(testjson).unwrap();
+        SecureHash {
+            tcid: test["tcId"].as_u32().unwrap(),
+            msg: hex::decode(test["msg"].as_str().unwrap()).unwrap(),
+        }
+    }
+
+    fn test_case(testjson: &str) -> AcvpTest {
+        let tc = json::parse(testjson).unwrap();
+        let tcid = tc["tcId"].as_u32().unwrap();
+        let test = secure_hash(testjson);
+        AcvpTest {
+            tcid,
+            test,
+            test_json: tc,
+        }
+    }
+
+    fn test_group(tgjson: &str) -> AcvpTestGroup {
+        let tg = json::parse(tgjson).unwrap();
+        let tgid = tg["tgId"].as_u32().unwrap();
+        let test_type = tg["testType"].as_str().unwrap().to_string();
+        let tests = tg["tests"]
+            .members()
+            .map(|tc| test_case(&tc.dump()))
+            .collect();
+        AcvpTestGroup {
+            tgid,
+            test_type,
+            tests,
+            testgroup_json: tg,
+        }
+    }
+
+    pub fn request(vector: &str) -> AcvpRequest {
+        let request = json::parse(vector).unwrap();
+        let vs = &request[1];
+        let vsid = vs["vsId"].as_u32().unwrap();
+        let algorithm = vs["algorithm"].as_str().unwrap().to_string();
+        let testgroups = vs["testGroups"]
+            .members()
+            .map(|tg| test_group(&tg.dump()))
+            .collect();
+        AcvpRequest {
+            vsid,
+            algorithm,
+            testgroups,
+            request_json: request,
+        }
+    }
+}
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+fn main() {
+    let vector = vector_set();
+    println!(
+        "vector set: {} groups x {} tests, {} MiB",
+        GROUPS,
+        TESTS,
+        vector.len() >> 20
+    );
+
+    let single = time(|| {
+        let req = AcvpRequest::<SecureHash>::new(&vector).unwrap();
+        assert_eq!(req.testgroups.len(), GROUPS);
+    });
+    let multi = time(|| {
+        let req = baseline::request(&vector);
+        assert_eq!(req.testgroups.len(), GROUPS);
+    });
+
+    println!("single pass:            {:>10.1?}", single);
+    println!("previous parser:        {:>10.1?}", multi);
+    println!(
+        "speedup:                {:>10.2}x",
+        multi.as_secs_f64() / single.as_secs_f64()
+    );
+}
@@ -0,0 +1,369 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use json::JsonValue;
+
+use crate::{
+    backend::{Backend, BackendTest},
+    dispatch::AnyResult,
+    msgauth::MsgAuthOutput,
+    parser::{GroupData, TestCase, TestResult},
+    util::{AcvpAlgorithm, Direction, TestType},
+    AcvpError, AcvpResult,
+};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AsconAeadGroupData {
+    pub algorithm: AcvpAlgorithm,
+    pub test_type: TestType,
+    pub direction: Direction,
+    pub nonce_masking: bool,
+}
+
+impl GroupData for AsconAeadGroupData {
+    fn new(algorithm: AcvpAlgorithm, test_type: TestType, tg: &JsonValue) -> AcvpResult<Self> {
+        let direction = Direction::from_string(&crate::util::get_acvp_str("direction", tg)?)?;
+
+        let mut nonce_masking = false;
+        if tg.has_key("nonceMasking") {
+            nonce_masking = crate::util::get_acvp_bool("nonceMasking", tg)?;
+        }
+
+        Ok(AsconAeadGroupData {
+            algorithm,
+            test_type,
+            direction,
+            nonce_masking,
+        })
+    }
+}
+
+/// Ascon-AEAD128 test case as specified in SP 800-232.
+///
+/// All lengths (`ad_len`, `payload_len` and `tag_len`) are in bits since the
+/// Ascon vector sets are bit oriented. The hex encoded values are padded to
+/// the next full byte. A tag verification failure of a decryption test is
+/// reported with a `false` result.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AsconAead {
+    algorithm: AcvpAlgorithm,
+    tcid: u32,
+    test_type: TestType,
+    direction: Direction,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::json_value"))]
+    res_json: JsonValue,
+    pub key: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub nonce_masking: bool,
+    pub second_key: Option<Vec<u8>>,
+    pub ad: Vec<u8>,
+    pub ad_len: usize,
+    pub payload: Vec<u8>,
+    pub payload_len: usize,
+    pub tag: Vec<u8>,
+    pub tag_len: usize,
+}
+
+impl TestCase for AsconAead {
+    type GroupData = AsconAeadGroupData;
+
+    fn new(test: &JsonValue, tgdata: &AsconAeadGroupData) -> AcvpResult<Self> {
+        let tcid = crate::util::get_acvp_u32("tcId", test)?;
+
+        let keyhex = crate::util::get_acvp_str("key", test)?;
+        let key = crate::util::hex2bin(&keyhex)?;
+
+        let noncehex = crate::util::get_acvp_str("nonce", test)?;
+        let nonce = crate::util::hex2bin(&noncehex)?;
+
+        let mut second_key = None;
+        if test.has_key("secondKey") {
+            let skhex = crate::util::get_acvp_str("secondKey", test)?;
+            second_key = Some(crate::util::hex2bin(&skhex)?);
+        } else if tgdata.nonce_masking {
+            return Err(AcvpError::invalid(
+                "Nonce masking requires 'secondKey' in Ascon testcase",
+            ));
+        }
+
+        let adhex = crate::util::get_acvp_str("ad", test)?;
+        let ad = crate::util::hex2bin(&adhex)?;
+        let ad_len = crate::util::get_acvp_u32("adLen", test)? as usize;
+
+        let payloadhex = match tgdata.direction {
+            Direction::Encrypt => crate::util::get_acvp_str("pt", test)?,
+            Direction::Decrypt => crate::util::get_acvp_str("ct", test)?,
+            _ => {
+                return Err(AcvpError::invalid(
+                    "Invalid direction for Ascon AEAD operation",
+                ));
+            }
+        };
+        let payload = crate::util::hex2bin(&payloadhex)?;
+        let payload_len = crate::util::get_acvp_u32("payloadLen", test)? as usize;
+
+        let mut tag = Vec::new();
+        if test.has_key("tag") {
+            let taghex = crate::util::get_acvp_str("tag", test)?;
+            tag = crate::util::hex2bin(&taghex)?;
+        }
+        let tag_len = crate::util::get_acvp_u32("tagLen", test)? as usize;
+
+        Ok(AsconAead {
+            algorithm: tgdata.algorithm,
+            tcid,
+            test_type: tgdata.test_type,
+            direction: tgdata.direction,
+            res_json: JsonValue::new_object(),
+            key,
+            nonce,
+            nonce_masking: tgdata.nonce_masking,
+            second_key,
+            ad,
+            ad_len,
+            payload,
+            payload_len,
+            tag,
+            tag_len,
+        })
+    }
+
+    fn get_result(&self) -> AcvpResult<JsonValue> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.clone())
+    }
+
+    fn dump_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.dump())
+    }
+
+    fn pretty_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.pretty(3))
+    }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        match res {
+            AnyResult::Bool(res) => self.set_result(res),
+            AnyResult::Bytes(res) => self.set_result(res),
+            AnyResult::MsgAuth(res) => self.set_result(res),
+            res => Err(res.unsupported()),
+        }
+    }
+
+    fn validate_result(&self, res: &JsonValue) -> Vec<String> {
+        let mut out = Vec::new();
+        if res.has_key("testPassed") {
+            return out;
+        }
+        let payload_len = self.payload_len.div_ceil(8);
+        if self.direction == Direction::Decrypt {
+            crate::validate::check_hex_len(res, "pt", payload_len, &mut out);
+        } else {
+            crate::validate::check_hex_len(res, "ct", payload_len, &mut out);
+            crate::validate::check_hex_len(res, "tag", self.tag_len.div_ceil(8), &mut out);
+        }
+        out
+    }
+}
+
+impl TestResult<MsgAuthOutput> for AsconAead {
+    fn set_result(&mut self, result: MsgAuthOutput) -> AcvpResult<()> {
+        if self.direction != Direction::Encrypt {
+            return Err(AcvpError::invalid_result(
+                "Ciphertext and tag can only be set for encryption",
+            ));
+        }
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            ct: hex::encode(result.out).to_ascii_uppercase(),
+            tag: hex::encode(result.tag).to_ascii_uppercase(),
+        };
+        Ok(())
+    }
+}
+
+impl TestResult<Vec<u8>> for AsconAead {
+    fn set_result(&mut self, result: Vec<u8>) -> AcvpResult<()> {
+        if self.direction != Direction::Decrypt {
+            return Err(AcvpError::invalid_result(
+                "Plaintext can only be set for decryption",
+            ));
+        }
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            pt: hex::encode(result).to_ascii_uppercase(),
+        };
+        Ok(())
+    }
+}
+
+impl TestResult<bool> for AsconAead {
+    fn set_result(&mut self, result: bool) -> AcvpResult<()> {
+        if self.direction != Direction::Decrypt {
+            return Err(AcvpError::invalid_result(
+                "Tag verification can only be reported for decryption",
+            ));
+        }
+        if result {
+            return Err(AcvpError::invalid_result(
+                "Successful decryptions must set the plaintext",
+            ));
+        }
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            testPassed: result,
+        };
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AsconHashGroupData {
+    pub algorithm: AcvpAlgorithm,
+    pub test_type: TestType,
+}
+
+impl GroupData for AsconHashGroupData {
+    fn new(algorithm: AcvpAlgorithm, test_type: TestType, _tg: &JsonValue) -> AcvpResult<Self> {
+        Ok(AsconHashGroupData {
+            algorithm,
+            test_type,
+        })
+    }
+}
+
+/// Ascon-Hash256, Ascon-XOF128 and Ascon-CXOF128 test case.
+///
+/// `len`, `out_len` and `cs_len` are in bits. For Ascon-Hash256 the output
+/// length is fixed to 256 bits, and the customization string is only present
+/// for Ascon-CXOF128.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AsconHash {
+    algorithm: AcvpAlgorithm,
+    tcid: u32,
+    test_type: TestType,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::json_value"))]
+    res_json: JsonValue,
+    pub msg: Vec<u8>,
+    pub len: usize,
+    pub out_len: usize,
+    pub cs: Vec<u8>,
+    pub cs_len: usize,
+}
+
+impl TestCase for AsconHash {
+    type GroupData = AsconHashGroupData;
+
+    fn new(test: &JsonValue, tgdata: &AsconHashGroupData) -> AcvpResult<Self> {
+        let tcid = crate::util::get_acvp_u32("tcId", test)?;
+
+        let msghex = crate::util::get_acvp_str("msg", test)?;
+        let msg = crate::util::hex2bin(&msghex)?;
+        let len = crate::util::get_acvp_u32("len", test)? as usize;
+
+        let mut out_len = 256;
+        if test.has_key("outLen") {
+            out_len = crate::util::get_acvp_u32("outLen", test)? as usize;
+        }
+
+        let mut cs = Vec::new();
+        let mut cs_len = 0;
+        if test.has_key("cs") {
+            let cshex = crate::util::get_acvp_str("cs", test)?;
+            cs = crate::util::hex2bin(&cshex)?;
+            cs_len = crate::util::get_acvp_u32("csLen", test)? as usize;
+        }
+
+        Ok(AsconHash {
+            algorithm: tgdata.algorithm,
+            tcid,
+            test_type: tgdata.test_type,
+            res_json: JsonValue::new_object(),
+            msg,
+            len,
+            out_len,
+            cs,
+            cs_len,
+        })
+    }
+
+    fn get_result(&self) -> AcvpResult<JsonValue> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.clone())
+    }
+
+    fn dump_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.dump())
+    }
+
+    fn pretty_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.pretty(3))
+    }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        match res {
+            AnyResult::Bytes(res) => self.set_result(res),
+            res => Err(res.unsupported()),
+        }
+    }
+
+    fn validate_result(&self, res: &JsonValue) -> Vec<String> {
+        let mut out = Vec::new();
+        crate::validate::check_hex_len(res, "md", self.out_len.div_ceil(8), &mut out);
+        out
+    }
+}
+
+impl TestResult<Vec<u8>> for AsconHash {
+    fn set_result(&mut self, md: Vec<u8>) -> AcvpResult<()> {
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            md: hex::encode(md).to_ascii_uppercase(),
+        };
+        Ok(())
+    }
+}
+
+impl BackendTest for AsconAead {
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        match self.direction {
+            Direction::Encrypt => {
+                let out = backend.ascon_aead_encrypt(self)?;
+                self.set_result(out)
+            }
+            _ => match backend.ascon_aead_decrypt(self)? {
+                Some(pt) => self.set_result(pt),
+                None => self.set_result(false),
+            },
+        }
+    }
+}
+
+impl BackendTest for AsconHash {
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        let md = backend.ascon_hash(self)?;
+        self.set_result(md)
+    }
+}
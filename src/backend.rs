@@ -0,0 +1,290 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Automatic execution of requests against the cryptographic module under
+//! test.
+//!
+//! The module implements the primitives of `Backend` it supports, and
+//! `AcvpRequest::run` (or `Executor::run_backend`) drives every test of a
+//! request through them: the test type and direction pick the primitive
+//! and the output is set as the result of the test. Primitives which are
+//! not implemented fail the tests needing them as unsupported.
+
+use crate::{
+    ascon::{AsconAead, AsconHash},
+    blkcipher::BlkCipherMCTOutput,
+    conditioning::Conditioning,
+    dispatch::AnyRequest,
+    drbg::DrbgInstance,
+    dsa::{Dsa, DsaKeyOutput, DsaPQGOutput, DsaSigOutput},
+    hash::LargeMsg,
+    ifc::{Ifc, IfcOutput},
+    msgauth::{MsgAuth, MsgAuthOutput},
+    parser::{AcvpRequest, AcvpTest, TestCase},
+    rsa::RsaPrimitive,
+    safeprimes::{SafePrimes, SafePrimesKeyOutput},
+    util::{AeadMode, BlockCipherAlg, CipherMode, Direction, DrbgType, HashAlg, MacAlg},
+    AcvpError, AcvpResult,
+};
+
+fn not_implemented(primitive: &str) -> AcvpError {
+    AcvpError::unsupported(format!("Backend does not implement {}", primitive))
+}
+
+/// Cryptographic primitives of the module under test.
+pub trait Backend {
+    fn hash(&self, _alg: HashAlg, _msg: &[u8]) -> AcvpResult<Vec<u8>> {
+        Err(not_implemented("hash"))
+    }
+
+    /// Run the Monte Carlo test of `alg` from `seed` and return the digest
+    /// of every outer iteration, by default with `hash::mct` over `hash`.
+    fn hash_mct(&self, alg: HashAlg, seed: &[u8]) -> AcvpResult<Vec<Vec<u8>>> {
+        crate::hash::mct(alg, seed, |msg| self.hash(alg, msg))
+    }
+
+    /// Hash the message of a large data test, by default the expanded
+    /// message is hashed with `hash`.
+    fn hash_ldt(&self, alg: HashAlg, msg: &LargeMsg) -> AcvpResult<Vec<u8>> {
+        self.hash(alg, &msg.expand())
+    }
+
+    /// Compute `out_len` bytes of output of the extendable output function
+    /// `alg`.
+    fn xof(&self, _alg: HashAlg, _msg: &[u8], _out_len: usize) -> AcvpResult<Vec<u8>> {
+        Err(not_implemented("xof"))
+    }
+
+    /// Run the SHAKE Monte Carlo test of `alg` from `seed`, with the output
+    /// length range in bits, by default with `hash::shake_mct` over `xof`.
+    fn shake_mct(
+        &self,
+        alg: HashAlg,
+        seed: &[u8],
+        min_out_len: usize,
+        max_out_len: usize,
+    ) -> AcvpResult<Vec<Vec<u8>>> {
+        crate::hash::shake_mct(seed, min_out_len, max_out_len, |msg, out_len| {
+            self.xof(alg, msg, out_len)
+        })
+    }
+
+    /// Encrypt `input` with `key`. `iv` is the IV, counter or XTS tweak, it
+    /// is empty for ECB. TDES keys are the concatenation of the three keys.
+    /// Monte Carlo tests call this with a single segment of input and the
+    /// chaining state as `iv`.
+    fn encrypt(
+        &self,
+        _alg: BlockCipherAlg,
+        _mode: CipherMode,
+        _key: &[u8],
+        _iv: &[u8],
+        _input: &[u8],
+    ) -> AcvpResult<Vec<u8>> {
+        Err(not_implemented("encrypt"))
+    }
+
+    fn decrypt(
+        &self,
+        _alg: BlockCipherAlg,
+        _mode: CipherMode,
+        _key: &[u8],
+        _iv: &[u8],
+        _input: &[u8],
+    ) -> AcvpResult<Vec<u8>> {
+        Err(not_implemented("decrypt"))
+    }
+
+    /// Run the Monte Carlo test of a block cipher and return the values of
+    /// every outer iteration, by default with `blkcipher::mct` over
+    /// `encrypt` or `decrypt`.
+    fn block_cipher_mct(
+        &self,
+        alg: BlockCipherAlg,
+        mode: CipherMode,
+        direction: Direction,
+        key: &[u8],
+        iv: &[u8],
+        input: &[u8],
+    ) -> AcvpResult<Vec<BlkCipherMCTOutput>> {
+        crate::blkcipher::mct(
+            alg,
+            mode,
+            direction,
+            key,
+            iv,
+            input,
+            |key, iv, input| match direction {
+                Direction::Encrypt => self.encrypt(alg, mode, key, iv, input),
+                _ => self.decrypt(alg, mode, key, iv, input),
+            },
+        )
+    }
+
+    /// Compute a HMAC or CMAC of `mac_len` bytes.
+    fn mac(&self, _alg: MacAlg, _key: &[u8], _msg: &[u8], _mac_len: usize) -> AcvpResult<Vec<u8>> {
+        Err(not_implemented("mac"))
+    }
+
+    /// Verify a HMAC or CMAC, by default the MAC is recomputed with `mac`.
+    fn mac_verify(&self, alg: MacAlg, key: &[u8], msg: &[u8], mac: &[u8]) -> AcvpResult<bool> {
+        Ok(self.mac(alg, key, msg, mac.len())? == mac)
+    }
+
+    /// Encrypt the message of an AEAD test, which carries the key, IV, salt
+    /// and AAD. GMAC tests have an empty message.
+    fn aead_encrypt(
+        &self,
+        _alg: BlockCipherAlg,
+        _mode: AeadMode,
+        _test: &MsgAuth,
+    ) -> AcvpResult<MsgAuthOutput> {
+        Err(not_implemented("aead_encrypt"))
+    }
+
+    /// Decrypt the message of an AEAD test and verify its tag, `None` if
+    /// the verification fails.
+    fn aead_decrypt(
+        &self,
+        _alg: BlockCipherAlg,
+        _mode: AeadMode,
+        _test: &MsgAuth,
+    ) -> AcvpResult<Option<Vec<u8>>> {
+        Err(not_implemented("aead_decrypt"))
+    }
+
+    /// Fill `buf` with random bytes, used for internally generated IVs and
+    /// salts.
+    fn random(&self, _buf: &mut [u8]) -> AcvpResult<()> {
+        Err(not_implemented("random"))
+    }
+
+    /// A fresh, uninstantiated DRBG of `drbg_type`.
+    fn drbg(&self, _drbg_type: DrbgType) -> AcvpResult<Box<dyn DrbgInstance>> {
+        Err(not_implemented("drbg"))
+    }
+
+    fn ascon_aead_encrypt(&self, _test: &AsconAead) -> AcvpResult<MsgAuthOutput> {
+        Err(not_implemented("ascon_aead_encrypt"))
+    }
+
+    /// Decrypt the payload of an Ascon-AEAD128 test and verify its tag,
+    /// `None` if the verification fails.
+    fn ascon_aead_decrypt(&self, _test: &AsconAead) -> AcvpResult<Option<Vec<u8>>> {
+        Err(not_implemented("ascon_aead_decrypt"))
+    }
+
+    /// Compute the Ascon-Hash256, Ascon-XOF128 or Ascon-CXOF128 output of a
+    /// test.
+    fn ascon_hash(&self, _test: &AsconHash) -> AcvpResult<Vec<u8>> {
+        Err(not_implemented("ascon_hash"))
+    }
+
+    /// Generate the DSA domain parameters of a pqgGen test, only `g` for
+    /// the generator tests.
+    fn dsa_pqg_gen(&self, _test: &Dsa) -> AcvpResult<DsaPQGOutput> {
+        Err(not_implemented("dsa_pqg_gen"))
+    }
+
+    fn dsa_pqg_ver(&self, _test: &Dsa) -> AcvpResult<bool> {
+        Err(not_implemented("dsa_pqg_ver"))
+    }
+
+    /// Generate a DSA key pair. The domain parameters are reported once per
+    /// test group, so every test of a group must use the same ones.
+    fn dsa_key_gen(&self, _test: &Dsa) -> AcvpResult<DsaKeyOutput> {
+        Err(not_implemented("dsa_key_gen"))
+    }
+
+    /// Sign the message of a DSA test. The domain parameters and the public
+    /// key are reported once per test group, so every test of a group must
+    /// use the same ones.
+    fn dsa_sig_gen(&self, _test: &Dsa) -> AcvpResult<DsaSigOutput> {
+        Err(not_implemented("dsa_sig_gen"))
+    }
+
+    fn dsa_sig_ver(&self, _test: &Dsa) -> AcvpResult<bool> {
+        Err(not_implemented("dsa_sig_ver"))
+    }
+
+    fn safe_primes_key_gen(&self, _test: &SafePrimes) -> AcvpResult<SafePrimesKeyOutput> {
+        Err(not_implemented("safe_primes_key_gen"))
+    }
+
+    fn safe_primes_key_ver(&self, _test: &SafePrimes) -> AcvpResult<bool> {
+        Err(not_implemented("safe_primes_key_ver"))
+    }
+
+    /// Perform a KAS-IFC-SSC or KTS-IFC operation in the role of the IUT.
+    fn ifc(&self, _test: &Ifc) -> AcvpResult<IfcOutput> {
+        Err(not_implemented("ifc"))
+    }
+
+    /// Check the shared secret or keying material of a KAS-IFC-SSC or
+    /// KTS-IFC validation test.
+    fn ifc_verify(&self, _test: &Ifc) -> AcvpResult<bool> {
+        Err(not_implemented("ifc_verify"))
+    }
+
+    /// Apply the RSA decryption or signature primitive, `None` if the input
+    /// is out of range for the key.
+    fn rsa_primitive(&self, _test: &RsaPrimitive) -> AcvpResult<Option<Vec<u8>>> {
+        Err(not_implemented("rsa_primitive"))
+    }
+
+    /// Compute the output of a conditioning component test.
+    fn conditioning(&self, _test: &Conditioning) -> AcvpResult<Vec<u8>> {
+        Err(not_implemented("conditioning"))
+    }
+}
+
+/// Test cases which can be executed against a `Backend`.
+pub trait BackendTest: TestCase {
+    /// Execute the test with `backend` and set its result.
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()>;
+}
+
+impl<T: BackendTest> BackendTest for AcvpTest<T> {
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        self.test.run(backend).map_err(|e| e.with_tcid(self.tcid))
+    }
+}
+
+impl<T: BackendTest> AcvpRequest<T> {
+    /// Execute every test without a result with `backend`, tests restored
+    /// with `AcvpRequest::resume` are skipped.
+    pub fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        let vsid = self.vsid;
+        for (tgid, test) in self.remaining_tests() {
+            test.run(backend)
+                .map_err(|e| e.with_vsid(vsid).with_tgid(tgid))?;
+        }
+        Ok(())
+    }
+}
+
+impl AnyRequest {
+    /// Execute every test without a result with `backend`. Fails for
+    /// custom algorithms, which `Backend` has no primitives for.
+    pub fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        match self {
+            Self::Hash(req) => req.run(backend),
+            Self::MsgAuth(req) => req.run(backend),
+            Self::BlockCipher(req) => req.run(backend),
+            Self::Rng(req) => req.run(backend),
+            Self::AsconAead(req) => req.run(backend),
+            Self::AsconHash(req) => req.run(backend),
+            Self::Dsa(req) => req.run(backend),
+            Self::SafePrimes(req) => req.run(backend),
+            Self::Ifc(req) => req.run(backend),
+            Self::Rsa(req) => req.run(backend),
+            Self::Conditioning(req) => req.run(backend),
+            req => Err(AcvpError::unsupported(format!(
+                "Algorithm {:?} cannot be run with a backend",
+                req.alg_type()
+            ))),
+        }
+    }
+}
@@ -6,17 +6,51 @@
 use json::JsonValue;
 
 use crate::{
-    parser::{TestCase, TestGroupData, TestResult},
-    util::{Direction, TestType},
+    backend::{Backend, BackendTest},
+    dispatch::AnyResult,
+    parser::{GroupData, TestCase, TestResult},
+    util::{AcvpAlgorithm, BlockCipherAlg, CipherMode, Direction, TestType},
+    validate::{MCT_ITERATIONS, TDES_MCT_ITERATIONS},
     AcvpError, AcvpResult,
 };
 
+/// Inner iterations of the AES and TDES Monte Carlo tests.
+const AES_MCT_INNER: usize = 1000;
+const TDES_MCT_INNER: usize = 10000;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlkCipherGroupData {
+    pub algorithm: AcvpAlgorithm,
+    pub test_type: TestType,
+    pub direction: Direction,
+}
+
+impl GroupData for BlkCipherGroupData {
+    fn new(algorithm: AcvpAlgorithm, test_type: TestType, tg: &JsonValue) -> AcvpResult<Self> {
+        let direction = Direction::from_string(&crate::util::get_acvp_str("direction", tg)?)?;
+        if direction != Direction::Encrypt && direction != Direction::Decrypt {
+            return Err(AcvpError::invalid(
+                "Invalid direction for block cipher operaion",
+            ));
+        }
+
+        Ok(BlkCipherGroupData {
+            algorithm,
+            test_type,
+            direction,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlockCipher {
-    algorithm: String,
+    algorithm: AcvpAlgorithm,
     test_type: TestType,
     direction: Direction,
     tcid: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::json_value"))]
     res_json: JsonValue,
     pub iv: Vec<u8>,
     pub input: Vec<u8>,
@@ -27,24 +61,17 @@ pub struct BlockCipher {
 }
 
 impl TestCase for BlockCipher {
-    fn new(testjson: &str, tgdata: &TestGroupData) -> AcvpResult<Self> {
-        let test = match json::parse(testjson) {
-            Ok(test) => test,
-            Err(_e) => {
-                return Err(AcvpError {
-                    code: -libc::EINVAL,
-                    message: "Failed to parse testcase JSON for SKCipher".to_string(),
-                });
-            }
-        };
-        let tcid = crate::util::get_acvp_u32("tcId", &test)?;
+    type GroupData = BlkCipherGroupData;
+
+    fn new(test: &JsonValue, tgdata: &BlkCipherGroupData) -> AcvpResult<Self> {
+        let tcid = crate::util::get_acvp_u32("tcId", test)?;
 
         let mut iv = Vec::new();
         if test.has_key("iv") {
-            let ivhex = crate::util::get_acvp_str("iv", &test)?;
+            let ivhex = crate::util::get_acvp_str("iv", test)?;
             iv = crate::util::hex2bin(&ivhex)?;
         } else if test.has_key("tweakValue") {
-            let ivhex = crate::util::get_acvp_str("tweakValue", &test)?;
+            let ivhex = crate::util::get_acvp_str("tweakValue", test)?;
             iv = crate::util::hex2bin(&ivhex)?;
         }
 
@@ -53,36 +80,35 @@ impl TestCase for BlockCipher {
         let mut key2 = Vec::new();
         let mut key3 = Vec::new();
         if test.has_key("key1") && test.has_key("key2") && test.has_key("key3") {
-            let key1hex = crate::util::get_acvp_str("key1", &test)?;
+            let key1hex = crate::util::get_acvp_str("key1", test)?;
             key1 = crate::util::hex2bin(&key1hex)?;
             key.extend(key1.iter().copied());
 
-            let key2hex = crate::util::get_acvp_str("key2", &test)?;
+            let key2hex = crate::util::get_acvp_str("key2", test)?;
             key2 = crate::util::hex2bin(&key2hex)?;
             key.extend(key2.iter().copied());
 
-            let key3hex = crate::util::get_acvp_str("key3", &test)?;
+            let key3hex = crate::util::get_acvp_str("key3", test)?;
             key3 = crate::util::hex2bin(&key3hex)?;
             key.extend(key3.iter().copied());
         } else if test.has_key("key") {
-            let keyhex = crate::util::get_acvp_str("key", &test)?;
+            let keyhex = crate::util::get_acvp_str("key", test)?;
             key = crate::util::hex2bin(&keyhex)?;
         }
 
         let inphex = match tgdata.direction {
-            Direction::Encrypt => crate::util::get_acvp_str("pt", &test)?,
-            Direction::Decrypt => crate::util::get_acvp_str("ct", &test)?,
+            Direction::Encrypt => crate::util::get_acvp_str("pt", test)?,
+            Direction::Decrypt => crate::util::get_acvp_str("ct", test)?,
             _ => {
-                return Err(AcvpError {
-                    code: -libc::EINVAL,
-                    message: "Invalid direction for block cipher operaion".to_string(),
-                });
+                return Err(AcvpError::invalid(
+                    "Invalid direction for block cipher operaion",
+                ));
             }
         };
         let input = crate::util::hex2bin(&inphex)?;
 
         Ok(BlockCipher {
-            algorithm: tgdata.algorithm.to_string(),
+            algorithm: tgdata.algorithm,
             test_type: tgdata.test_type,
             direction: tgdata.direction,
             tcid,
@@ -98,33 +124,51 @@ impl TestCase for BlockCipher {
 
     fn get_result(&self) -> AcvpResult<JsonValue> {
         if self.res_json.is_empty() {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "The result is not yet set, call set_*_result APIs".to_string(),
-            });
+            return Err(AcvpError::result_not_set());
         }
         Ok(self.res_json.clone())
     }
 
     fn dump_result(&self) -> AcvpResult<String> {
         if self.res_json.is_empty() {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "The result is not yet set, call set_*_result APIs".to_string(),
-            });
+            return Err(AcvpError::result_not_set());
         }
         Ok(self.res_json.dump())
     }
 
     fn pretty_result(&self) -> AcvpResult<String> {
         if self.res_json.is_empty() {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "The result is not yet set, call set_*_result APIs".to_string(),
-            });
+            return Err(AcvpError::result_not_set());
         }
         Ok(self.res_json.pretty(3))
     }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        match res {
+            AnyResult::Bytes(res) => self.set_result(res),
+            AnyResult::BlkCipherMct(res) => self.set_result(res),
+            res => Err(res.unsupported()),
+        }
+    }
+
+    fn validate_result(&self, res: &JsonValue) -> Vec<String> {
+        let mut out = Vec::new();
+        let field = match self.direction {
+            Direction::Decrypt => "pt",
+            _ => "ct",
+        };
+        if self.test_type == TestType::MCT {
+            let iterations = match self.algorithm {
+                AcvpAlgorithm::BlockCipher(BlockCipherAlg::TDES, _) => TDES_MCT_ITERATIONS,
+                _ => MCT_ITERATIONS,
+            };
+            let entries = crate::validate::check_mct_results(res, iterations, &mut out);
+            crate::validate::check_mct_hex_len(&entries, field, self.input.len(), &mut out);
+        } else {
+            crate::validate::check_hex_len(res, field, self.input.len(), &mut out);
+        }
+        out
+    }
 }
 
 impl TestResult<Vec<u8>> for BlockCipher {
@@ -141,6 +185,7 @@ impl TestResult<Vec<BlkCipherMCTOutput>> for BlockCipher {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlkCipherMCTOutput {
     pub key: Vec<u8>,
     pub key1: Vec<u8>,
@@ -204,7 +249,7 @@ impl BlockCipher {
         let mut results = JsonValue::new_array();
         for out in outvec {
             let mut res = JsonValue::new_object();
-            if self.algorithm.contains("AES") {
+            if let AcvpAlgorithm::BlockCipher(BlockCipherAlg::AES, _) = self.algorithm {
                 res["key"] = hex::encode(out.key).to_ascii_uppercase().into();
             }
             if !out.iv.is_empty() {
@@ -221,7 +266,7 @@ impl BlockCipher {
                 }
                 _ => {}
             }
-            if self.algorithm.contains("TDES") {
+            if let AcvpAlgorithm::BlockCipher(BlockCipherAlg::TDES, _) = self.algorithm {
                 res["key1"] = hex::encode(out.key1).to_ascii_uppercase().into();
                 res["key2"] = hex::encode(out.key2).to_ascii_uppercase().into();
                 res["key3"] = hex::encode(out.key3).to_ascii_uppercase().into();
@@ -229,10 +274,10 @@ impl BlockCipher {
             match results.push(res) {
                 Ok(()) => {}
                 Err(_e) => {
-                    return Err(AcvpError {
-                        code: -1,
-                        message: "Unexpected error pushing to JsonValue array".to_string(),
-                    });
+                    return Err(AcvpError::with_code(
+                        -1,
+                        "Unexpected error pushing to JsonValue array",
+                    ));
                 }
             }
         }
@@ -243,3 +288,264 @@ impl BlockCipher {
         Ok(())
     }
 }
+
+/// Set odd parity on every byte of a DES key.
+fn set_odd_parity(key: &mut [u8]) {
+    for b in key.iter_mut() {
+        let even = (*b & 0xfe).count_ones() % 2 == 0;
+        *b = (*b & 0xfe) | even as u8;
+    }
+}
+
+/// The last `nbytes` bytes of the concatenation of `segs`, each `seg_bits`
+/// long.
+fn stream_tail(segs: &[Vec<u8>], seg_bits: usize, nbytes: usize) -> Vec<u8> {
+    if seg_bits == 1 {
+        return segs[segs.len() - nbytes * 8..]
+            .chunks(8)
+            .map(|bits| bits.iter().fold(0, |byte, bit| (byte << 1) | (bit[0] >> 7)))
+            .collect();
+    }
+    let mut tail = Vec::new();
+    for seg in segs.iter().rev() {
+        if tail.len() >= nbytes {
+            break;
+        }
+        tail.splice(0..0, seg.iter().copied());
+    }
+    tail.split_off(tail.len() - nbytes)
+}
+
+/// Segment `j` of `iv`: bit `j` for CFB1, byte `j` for CFB8 and the whole
+/// block otherwise.
+fn iv_segment(iv: &[u8], seg_bits: usize, j: usize) -> Vec<u8> {
+    match seg_bits {
+        1 => vec![(iv[j / 8] << (j % 8)) & 0x80],
+        8 => vec![iv[j]],
+        _ => iv.to_vec(),
+    }
+}
+
+/// IV of the next segment, after `input` was processed into `output` with
+/// `iv`.
+fn next_iv(
+    mode: CipherMode,
+    direction: Direction,
+    iv: &[u8],
+    input: &[u8],
+    output: &[u8],
+) -> Vec<u8> {
+    let ct = match direction {
+        Direction::Encrypt => output,
+        _ => input,
+    };
+    match mode {
+        CipherMode::OFB => input.iter().zip(output).map(|(i, o)| i ^ o).collect(),
+        CipherMode::CFB1 => {
+            let mut next = iv.to_vec();
+            for i in 0..next.len() {
+                let carry = next.get(i + 1).map_or(ct[0] >> 7, |b| b >> 7);
+                next[i] = (next[i] << 1) | carry;
+            }
+            next
+        }
+        CipherMode::CFB8 => {
+            let mut next = iv[1..].to_vec();
+            next.push(ct[0]);
+            next
+        }
+        _ => ct.to_vec(),
+    }
+}
+
+/// Run the Monte Carlo test of a block cipher and return the values of
+/// every outer iteration.
+///
+/// `cipher` performs a single operation in `mode` and `direction` with the
+/// given key and IV on one segment of input: a bit for CFB1, a byte for
+/// CFB8 and a block otherwise. The IV passed along carries the chaining
+/// state, so `cipher` needs to keep no state between calls. TDES keys are
+/// the concatenation of the three keys. After every outer iteration the
+/// TDES keys are updated with the last 192 output bits, keeping the keying
+/// option of the initial keys, and set to odd parity.
+pub fn mct<F>(
+    alg: BlockCipherAlg,
+    mode: CipherMode,
+    direction: Direction,
+    key: &[u8],
+    iv: &[u8],
+    input: &[u8],
+    mut cipher: F,
+) -> AcvpResult<Vec<BlkCipherMCTOutput>>
+where
+    F: FnMut(&[u8], &[u8], &[u8]) -> AcvpResult<Vec<u8>>,
+{
+    let (block, outer, inner) = match alg {
+        BlockCipherAlg::AES => (16, MCT_ITERATIONS, AES_MCT_INNER),
+        BlockCipherAlg::TDES => (8, TDES_MCT_ITERATIONS, TDES_MCT_INNER),
+    };
+    let seg_bits = match mode {
+        CipherMode::CFB1 => 1,
+        CipherMode::CFB8 => 8,
+        CipherMode::ECB
+        | CipherMode::CBC
+        | CipherMode::OFB
+        | CipherMode::CFB64
+        | CipherMode::CFB128 => block * 8,
+        CipherMode::CTR | CipherMode::XTS => {
+            return Err(AcvpError::unsupported(format!(
+                "Monte Carlo test of {:?} is not defined",
+                mode
+            )));
+        }
+    };
+    // TDES keying option 2 keeps Key1 and Key3 equal, option 3 all keys.
+    let (same_k1_k2, same_k1_k3) = match alg {
+        BlockCipherAlg::AES if ![16, 24, 32].contains(&key.len()) => {
+            return Err(AcvpError::invalid(
+                "AES key must be 16, 24 or 32 bytes long",
+            ));
+        }
+        BlockCipherAlg::AES => (false, false),
+        BlockCipherAlg::TDES if key.len() != 24 => {
+            return Err(AcvpError::invalid("TDES key must be 24 bytes long"));
+        }
+        BlockCipherAlg::TDES => (key[..8] == key[8..16], key[..8] == key[16..]),
+    };
+    if mode != CipherMode::ECB && iv.len() != block {
+        return Err(AcvpError::invalid(format!(
+            "IV must be {} bytes long",
+            block
+        )));
+    }
+    let segs_per_block = block * 8 / seg_bits;
+
+    let mut key = key.to_vec();
+    let mut iv = iv.to_vec();
+    let mut input = input.to_vec();
+    let mut results = Vec::with_capacity(outer);
+    for _ in 0..outer {
+        let mut outs: Vec<Vec<u8>> = Vec::with_capacity(inner);
+        let mut chain = iv.clone();
+        let mut cur = input.clone();
+        for j in 0..inner {
+            let out = cipher(&key, &chain, &cur)?;
+            if out.len() != cur.len() {
+                return Err(AcvpError::invalid_result(format!(
+                    "Cipher returned {} bytes, expected {}",
+                    out.len(),
+                    cur.len()
+                )));
+            }
+            if mode != CipherMode::ECB {
+                chain = next_iv(mode, direction, &chain, &cur, &out);
+            }
+            cur = if mode == CipherMode::ECB {
+                out.clone()
+            } else if j < segs_per_block {
+                iv_segment(&iv, seg_bits, j)
+            } else {
+                outs[j - segs_per_block].clone()
+            };
+            outs.push(out);
+        }
+
+        let last = outs[inner - 1].clone();
+        results.push(match alg {
+            BlockCipherAlg::AES => {
+                BlkCipherMCTOutput::new_aes(key.clone(), iv.clone(), input, last.clone())
+            }
+            BlockCipherAlg::TDES => BlkCipherMCTOutput::new_tdes(
+                key[..8].to_vec(),
+                key[8..16].to_vec(),
+                key[16..].to_vec(),
+                iv.clone(),
+                input,
+                last.clone(),
+            ),
+        });
+
+        let tail = stream_tail(&outs, seg_bits, key.len());
+        match alg {
+            BlockCipherAlg::AES => {
+                key.iter_mut().zip(&tail).for_each(|(k, t)| *k ^= t);
+            }
+            BlockCipherAlg::TDES => {
+                // Key1 takes the last 64 bits, Key3 the first.
+                for (k, t) in key.chunks_mut(8).zip(tail.chunks(8).rev()) {
+                    k.iter_mut().zip(t).for_each(|(k, t)| *k ^= t);
+                }
+                let k1 = key[..8].to_vec();
+                if same_k1_k2 && same_k1_k3 {
+                    key[8..16].copy_from_slice(&k1);
+                }
+                if same_k1_k3 {
+                    key[16..].copy_from_slice(&k1);
+                }
+                set_odd_parity(&mut key);
+            }
+        }
+        if mode == CipherMode::ECB {
+            input = last;
+        } else {
+            iv = stream_tail(&outs, seg_bits, block);
+            input = outs[inner - 1 - segs_per_block].clone();
+        }
+    }
+    Ok(results)
+}
+
+impl BlockCipher {
+    /// Run the Monte Carlo test of this test case with `cipher`, see `mct`.
+    pub fn mct<F>(&self, cipher: F) -> AcvpResult<Vec<BlkCipherMCTOutput>>
+    where
+        F: FnMut(&[u8], &[u8], &[u8]) -> AcvpResult<Vec<u8>>,
+    {
+        match self.algorithm {
+            AcvpAlgorithm::BlockCipher(alg, mode) => mct(
+                alg,
+                mode,
+                self.direction,
+                &self.key,
+                &self.iv,
+                &self.input,
+                cipher,
+            ),
+            _ => Err(AcvpError::invalid("Algorithm is not a block cipher")),
+        }
+    }
+}
+
+impl BackendTest for BlockCipher {
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        let (alg, mode) = match self.algorithm {
+            AcvpAlgorithm::BlockCipher(alg, mode) => (alg, mode),
+            _ => return Err(AcvpError::invalid("Algorithm is not a block cipher")),
+        };
+        match (self.test_type, self.direction) {
+            (TestType::AFT, Direction::Encrypt) => {
+                let ct = backend.encrypt(alg, mode, &self.key, &self.iv, &self.input)?;
+                self.set_result(ct)
+            }
+            (TestType::AFT, Direction::Decrypt) => {
+                let pt = backend.decrypt(alg, mode, &self.key, &self.iv, &self.input)?;
+                self.set_result(pt)
+            }
+            (TestType::MCT, direction) => {
+                let res = backend.block_cipher_mct(
+                    alg,
+                    mode,
+                    direction,
+                    &self.key,
+                    &self.iv,
+                    &self.input,
+                )?;
+                self.set_result(res)
+            }
+            (test_type, _) => Err(AcvpError::unsupported(format!(
+                "Test type {:?} is not supported for block ciphers",
+                test_type
+            ))),
+        }
+    }
+}
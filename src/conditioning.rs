@@ -0,0 +1,191 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use json::JsonValue;
+
+use crate::{
+    backend::{Backend, BackendTest},
+    dispatch::AnyResult,
+    parser::{GroupData, TestCase, TestResult},
+    util::{AcvpAlgorithm, HashAlg, TestType},
+    AcvpError, AcvpResult,
+};
+
+/// Vetted conditioning function of an SP800-90B entropy source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConditioningMode {
+    BlockCipherDf,
+    CbcMac,
+    HashDf,
+}
+
+impl std::str::FromStr for ConditioningMode {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "BlockCipher_DF" => Ok(Self::BlockCipherDf),
+            "CBC-MAC" | "AES-CBC-MAC" => Ok(Self::CbcMac),
+            "Hash_DF" => Ok(Self::HashDf),
+            _ => Err(AcvpError::unsupported(format!(
+                "Conditioning component mode '{}' is not valid",
+                s
+            ))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+/// ConditioningComponent group parameters, all lengths are in bits.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConditioningGroupData {
+    pub algorithm: AcvpAlgorithm,
+    pub test_type: TestType,
+    pub mode: ConditioningMode,
+    pub hash_alg: Option<HashAlg>,
+    pub key_len: usize,
+    pub payload_len: usize,
+    pub output_len: usize,
+}
+
+impl GroupData for ConditioningGroupData {
+    fn new(algorithm: AcvpAlgorithm, test_type: TestType, tg: &JsonValue) -> AcvpResult<Self> {
+        let mode = match algorithm {
+            AcvpAlgorithm::Conditioning(mode) => mode,
+            _ => {
+                return Err(AcvpError::invalid("Algorithm is not ConditioningComponent"));
+            }
+        };
+
+        let mut hash_alg = None;
+        let mut key_len = 0;
+        if mode == ConditioningMode::HashDf {
+            hash_alg = Some(crate::util::get_acvp_str("hashAlg", tg)?.parse()?);
+        } else {
+            key_len = crate::util::get_acvp_u32("keyLen", tg)? as usize;
+        }
+
+        let mut payload_len = 0;
+        if tg.has_key("payloadLen") {
+            payload_len = crate::util::get_acvp_u32("payloadLen", tg)? as usize;
+        }
+
+        let output_len = crate::util::get_acvp_u32("outputLen", tg)? as usize;
+
+        Ok(ConditioningGroupData {
+            algorithm,
+            test_type,
+            mode,
+            hash_alg,
+            key_len,
+            payload_len,
+            output_len,
+        })
+    }
+}
+
+/// ConditioningComponent test case.
+///
+/// `payload_len` and `output_len` are in bits. The key is only present for
+/// the block cipher based modes, `hash_alg` only for Hash_DF.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conditioning {
+    algorithm: AcvpAlgorithm,
+    tcid: u32,
+    test_type: TestType,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::json_value"))]
+    res_json: JsonValue,
+    pub mode: ConditioningMode,
+    pub hash_alg: Option<HashAlg>,
+    pub key_len: usize,
+    pub key: Vec<u8>,
+    pub payload: Vec<u8>,
+    pub payload_len: usize,
+    pub output_len: usize,
+}
+
+impl TestCase for Conditioning {
+    type GroupData = ConditioningGroupData;
+
+    fn new(test: &JsonValue, tgdata: &ConditioningGroupData) -> AcvpResult<Self> {
+        let tcid = crate::util::get_acvp_u32("tcId", test)?;
+        let mode = tgdata.mode;
+
+        let mut key = Vec::new();
+        if mode != ConditioningMode::HashDf {
+            let keyhex = crate::util::get_acvp_str("key", test)?;
+            key = crate::util::hex2bin(&keyhex)?;
+        }
+
+        let payloadhex = crate::util::get_acvp_str("payload", test)?;
+        let payload = crate::util::hex2bin(&payloadhex)?;
+
+        let mut payload_len = tgdata.payload_len;
+        if test.has_key("payloadLen") {
+            payload_len = crate::util::get_acvp_u32("payloadLen", test)? as usize;
+        }
+
+        Ok(Conditioning {
+            algorithm: tgdata.algorithm,
+            tcid,
+            test_type: tgdata.test_type,
+            res_json: JsonValue::new_object(),
+            mode,
+            hash_alg: tgdata.hash_alg,
+            key_len: tgdata.key_len,
+            key,
+            payload,
+            payload_len,
+            output_len: tgdata.output_len,
+        })
+    }
+
+    fn get_result(&self) -> AcvpResult<JsonValue> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.clone())
+    }
+
+    fn dump_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.dump())
+    }
+
+    fn pretty_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.pretty(3))
+    }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        match res {
+            AnyResult::Bytes(res) => self.set_result(res),
+            res => Err(res.unsupported()),
+        }
+    }
+}
+
+impl TestResult<Vec<u8>> for Conditioning {
+    fn set_result(&mut self, output: Vec<u8>) -> AcvpResult<()> {
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            output: hex::encode(output).to_ascii_uppercase(),
+        };
+        Ok(())
+    }
+}
+
+impl BackendTest for Conditioning {
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        let out = backend.conditioning(self)?;
+        self.set_result(out)
+    }
+}
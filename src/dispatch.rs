@@ -0,0 +1,350 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::any::Any;
+
+use json::JsonValue;
+
+use crate::{
+    ascon::{AsconAead, AsconHash},
+    blkcipher::{BlkCipherMCTOutput, BlockCipher},
+    conditioning::Conditioning,
+    drbg::Drbg,
+    dsa::{Dsa, DsaKeyOutput, DsaPQGOutput, DsaSigOutput},
+    hash::SecureHash,
+    ifc::{Ifc, IfcOutput},
+    msgauth::{AeadOutput, MsgAuth, MsgAuthOutput},
+    parser::{AcvpRequest, AcvpTest, TestCase},
+    response::AcvpResponse,
+    rsa::RsaPrimitive,
+    safeprimes::{SafePrimes, SafePrimesKeyOutput},
+    util::AcvpAlgorithm,
+    AcvpError, AcvpResult,
+};
+
+/// Result of any test case, dispatched to the matching `TestResult`
+/// implementation of the test by `AnyTest::set_any_result`.
+#[derive(Debug, Clone)]
+pub enum AnyResult {
+    Bool(bool),
+    Bytes(Vec<u8>),
+    BytesVec(Vec<Vec<u8>>),
+    BlkCipherMct(Vec<BlkCipherMCTOutput>),
+    MsgAuth(MsgAuthOutput),
+    Aead(AeadOutput),
+    DsaPqg(DsaPQGOutput),
+    DsaKey(DsaKeyOutput),
+    DsaSig(DsaSigOutput),
+    SafePrimesKey(SafePrimesKeyOutput),
+    Ifc(IfcOutput),
+}
+
+impl AnyResult {
+    /// Error for a result type the test case does not accept.
+    pub fn unsupported(&self) -> AcvpError {
+        AcvpError::invalid_result(format!(
+            "Result type {} is not supported by the test",
+            self.name()
+        ))
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Bool(_) => "Bool",
+            Self::Bytes(_) => "Bytes",
+            Self::BytesVec(_) => "BytesVec",
+            Self::BlkCipherMct(_) => "BlkCipherMct",
+            Self::MsgAuth(_) => "MsgAuth",
+            Self::Aead(_) => "Aead",
+            Self::DsaPqg(_) => "DsaPqg",
+            Self::DsaKey(_) => "DsaKey",
+            Self::DsaSig(_) => "DsaSig",
+            Self::SafePrimesKey(_) => "SafePrimesKey",
+            Self::Ifc(_) => "Ifc",
+        }
+    }
+}
+
+impl From<bool> for AnyResult {
+    fn from(res: bool) -> Self {
+        Self::Bool(res)
+    }
+}
+
+impl From<Vec<u8>> for AnyResult {
+    fn from(res: Vec<u8>) -> Self {
+        Self::Bytes(res)
+    }
+}
+
+impl From<Vec<Vec<u8>>> for AnyResult {
+    fn from(res: Vec<Vec<u8>>) -> Self {
+        Self::BytesVec(res)
+    }
+}
+
+impl From<Vec<BlkCipherMCTOutput>> for AnyResult {
+    fn from(res: Vec<BlkCipherMCTOutput>) -> Self {
+        Self::BlkCipherMct(res)
+    }
+}
+
+impl From<MsgAuthOutput> for AnyResult {
+    fn from(res: MsgAuthOutput) -> Self {
+        Self::MsgAuth(res)
+    }
+}
+
+impl From<AeadOutput> for AnyResult {
+    fn from(res: AeadOutput) -> Self {
+        Self::Aead(res)
+    }
+}
+
+impl From<DsaPQGOutput> for AnyResult {
+    fn from(res: DsaPQGOutput) -> Self {
+        Self::DsaPqg(res)
+    }
+}
+
+impl From<DsaKeyOutput> for AnyResult {
+    fn from(res: DsaKeyOutput) -> Self {
+        Self::DsaKey(res)
+    }
+}
+
+impl From<DsaSigOutput> for AnyResult {
+    fn from(res: DsaSigOutput) -> Self {
+        Self::DsaSig(res)
+    }
+}
+
+impl From<SafePrimesKeyOutput> for AnyResult {
+    fn from(res: SafePrimesKeyOutput) -> Self {
+        Self::SafePrimesKey(res)
+    }
+}
+
+impl From<IfcOutput> for AnyResult {
+    fn from(res: IfcOutput) -> Self {
+        Self::Ifc(res)
+    }
+}
+
+/// Type erased test case of any vector set.
+///
+/// The test case data is reached through `test_case()`, which can be
+/// downcast to the concrete type, e.g. `SecureHash`, once the algorithm of
+/// the request is known.
+pub trait AnyTest {
+    fn tcid(&self) -> u32;
+    fn test_case(&self) -> &dyn Any;
+    fn test_case_mut(&mut self) -> &mut dyn Any;
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()>;
+    fn get_result(&self) -> AcvpResult<JsonValue>;
+}
+
+impl<T: TestCase + 'static> AnyTest for AcvpTest<T> {
+    fn tcid(&self) -> u32 {
+        self.tcid
+    }
+
+    fn test_case(&self) -> &dyn Any {
+        &self.test
+    }
+
+    fn test_case_mut(&mut self) -> &mut dyn Any {
+        &mut self.test
+    }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        TestCase::set_any_result(self, res)
+    }
+
+    fn get_result(&self) -> AcvpResult<JsonValue> {
+        TestCase::get_result(self)
+    }
+}
+
+/// Type erased request of any vector set.
+pub trait DynRequest {
+    fn vsid(&self) -> u32;
+    fn alg_type(&self) -> AcvpAlgorithm;
+    fn tests(&self) -> Vec<&dyn AnyTest>;
+    fn tests_mut(&mut self) -> Vec<&mut dyn AnyTest>;
+    fn get_result(&self) -> AcvpResult<JsonValue>;
+    fn attach_response(&mut self, response: &AcvpResponse) -> AcvpResult<()>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: TestCase + 'static> DynRequest for AcvpRequest<T> {
+    fn vsid(&self) -> u32 {
+        self.vsid
+    }
+
+    fn alg_type(&self) -> AcvpAlgorithm {
+        self.alg_type
+    }
+
+    fn tests(&self) -> Vec<&dyn AnyTest> {
+        let mut tests: Vec<&dyn AnyTest> = Vec::new();
+        for tg in &self.testgroups {
+            for test in &tg.tests {
+                tests.push(test);
+            }
+        }
+        tests
+    }
+
+    fn tests_mut(&mut self) -> Vec<&mut dyn AnyTest> {
+        let mut tests: Vec<&mut dyn AnyTest> = Vec::new();
+        for tg in &mut self.testgroups {
+            for test in &mut tg.tests {
+                tests.push(test);
+            }
+        }
+        tests
+    }
+
+    fn get_result(&self) -> AcvpResult<JsonValue> {
+        AcvpRequest::get_result(self)
+    }
+
+    fn attach_response(&mut self, response: &AcvpResponse) -> AcvpResult<()> {
+        AcvpRequest::attach_response(self, response)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Request of any supported vector set, typed by its algorithm family.
+pub enum AnyRequest {
+    Hash(AcvpRequest<SecureHash>),
+    MsgAuth(AcvpRequest<MsgAuth>),
+    BlockCipher(AcvpRequest<BlockCipher>),
+    Rng(AcvpRequest<Drbg>),
+    AsconAead(AcvpRequest<AsconAead>),
+    AsconHash(AcvpRequest<AsconHash>),
+    Dsa(AcvpRequest<Dsa>),
+    SafePrimes(AcvpRequest<SafePrimes>),
+    Ifc(AcvpRequest<Ifc>),
+    Rsa(AcvpRequest<RsaPrimitive>),
+    Conditioning(AcvpRequest<Conditioning>),
+    Custom(Box<dyn DynRequest>),
+}
+
+impl AnyRequest {
+    /// Parse a vector set of any supported algorithm.
+    pub fn new(vector: &str) -> AcvpResult<Self> {
+        let request = match json::parse(vector) {
+            Ok(req) => req,
+            Err(e) => {
+                return Err(AcvpError::parse_json("Invalid ACVP Request JSON", e));
+            }
+        };
+        Self::from_json(request)
+    }
+
+    /// Construct the request of an already parsed vector set.
+    pub fn from_json(request: JsonValue) -> AcvpResult<Self> {
+        let req = match crate::util::get_algorithm_type_json(&request)? {
+            AcvpAlgorithm::Hash(_) => Self::Hash(AcvpRequest::from_json(request)?),
+            AcvpAlgorithm::MsgAuth(_) => Self::MsgAuth(AcvpRequest::from_json(request)?),
+            AcvpAlgorithm::BlockCipher(..) => Self::BlockCipher(AcvpRequest::from_json(request)?),
+            AcvpAlgorithm::Rng(_) => Self::Rng(AcvpRequest::from_json(request)?),
+            AcvpAlgorithm::AsconAead => Self::AsconAead(AcvpRequest::from_json(request)?),
+            AcvpAlgorithm::AsconHash(_) => Self::AsconHash(AcvpRequest::from_json(request)?),
+            AcvpAlgorithm::Dsa(_) => Self::Dsa(AcvpRequest::from_json(request)?),
+            AcvpAlgorithm::SafePrimes(_) => Self::SafePrimes(AcvpRequest::from_json(request)?),
+            AcvpAlgorithm::Ifc(_) => Self::Ifc(AcvpRequest::from_json(request)?),
+            AcvpAlgorithm::Rsa(_) => Self::Rsa(AcvpRequest::from_json(request)?),
+            AcvpAlgorithm::Conditioning(_) => Self::Conditioning(AcvpRequest::from_json(request)?),
+            AcvpAlgorithm::Custom(id) => match crate::registry::loader(id) {
+                Some(loader) => Self::Custom(loader(request)?),
+                None => {
+                    return Err(AcvpError::unsupported(format!(
+                        "No handler registered for custom algorithm {}",
+                        id
+                    )));
+                }
+            },
+            AcvpAlgorithm::Nil => {
+                return Err(AcvpError::missing_field("algorithm", "str"));
+            }
+        };
+        Ok(req)
+    }
+
+    pub fn as_dyn(&self) -> &dyn DynRequest {
+        match self {
+            Self::Hash(req) => req,
+            Self::MsgAuth(req) => req,
+            Self::BlockCipher(req) => req,
+            Self::Rng(req) => req,
+            Self::AsconAead(req) => req,
+            Self::AsconHash(req) => req,
+            Self::Dsa(req) => req,
+            Self::SafePrimes(req) => req,
+            Self::Ifc(req) => req,
+            Self::Rsa(req) => req,
+            Self::Conditioning(req) => req,
+            Self::Custom(req) => req.as_ref(),
+        }
+    }
+
+    pub fn as_dyn_mut(&mut self) -> &mut dyn DynRequest {
+        match self {
+            Self::Hash(req) => req,
+            Self::MsgAuth(req) => req,
+            Self::BlockCipher(req) => req,
+            Self::Rng(req) => req,
+            Self::AsconAead(req) => req,
+            Self::AsconHash(req) => req,
+            Self::Dsa(req) => req,
+            Self::SafePrimes(req) => req,
+            Self::Ifc(req) => req,
+            Self::Rsa(req) => req,
+            Self::Conditioning(req) => req,
+            Self::Custom(req) => req.as_mut(),
+        }
+    }
+
+    pub fn alg_type(&self) -> AcvpAlgorithm {
+        self.as_dyn().alg_type()
+    }
+
+    pub fn tests(&self) -> Vec<&dyn AnyTest> {
+        self.as_dyn().tests()
+    }
+
+    pub fn tests_mut(&mut self) -> Vec<&mut dyn AnyTest> {
+        self.as_dyn_mut().tests_mut()
+    }
+
+    pub fn get_result(&self) -> AcvpResult<JsonValue> {
+        self.as_dyn().get_result()
+    }
+
+    pub fn attach_response(&mut self, response: &AcvpResponse) -> AcvpResult<()> {
+        self.as_dyn_mut().attach_response(response)
+    }
+
+    pub fn dump_result(&self) -> AcvpResult<String> {
+        let res = self.get_result()?;
+        Ok(res.dump())
+    }
+
+    pub fn pretty_result(&self) -> AcvpResult<String> {
+        let res = self.get_result()?;
+        Ok(res.pretty(3))
+    }
+}
@@ -6,20 +6,31 @@
 use json::JsonValue;
 
 use crate::{
-    parser::{TestCase, TestResult},
-    util::TestType,
+    backend::{Backend, BackendTest},
+    dispatch::AnyResult,
+    parser::{GroupData, TestCase, TestResult},
+    util::{AcvpAlgorithm, DrbgType, TestType},
     AcvpError, AcvpResult,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DrbgMode {
     SHA1,
+    SHA224,
     SHA256,
     SHA384,
     SHA512,
+    SHA512_224,
+    SHA512_256,
+    SHA3_224,
+    SHA3_256,
+    SHA3_384,
+    SHA3_512,
     AES128,
     AES192,
     AES256,
+    TDES,
     Nil,
 }
 
@@ -27,39 +38,144 @@ impl std::str::FromStr for DrbgMode {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "SHA-1" => Ok(Self::SHA1),
+            "SHA2-224" => Ok(Self::SHA224),
             "SHA2-256" => Ok(Self::SHA256),
             "SHA2-384" => Ok(Self::SHA384),
             "SHA2-512" => Ok(Self::SHA512),
+            "SHA2-512/224" => Ok(Self::SHA512_224),
+            "SHA2-512/256" => Ok(Self::SHA512_256),
+            "SHA3-224" => Ok(Self::SHA3_224),
+            "SHA3-256" => Ok(Self::SHA3_256),
+            "SHA3-384" => Ok(Self::SHA3_384),
+            "SHA3-512" => Ok(Self::SHA3_512),
             "AES-128" => Ok(Self::AES128),
             "AES-192" => Ok(Self::AES192),
             "AES-256" => Ok(Self::AES256),
-            _ => {
-                return Err(AcvpError {
-                    code: -libc::EINVAL,
-                    message: format!("Mode '{}' is not valid", s),
-                });
-            }
+            "TDES" => Ok(Self::TDES),
+            _ => Err(AcvpError::unsupported(format!("Mode '{}' is not valid", s))),
         }
     }
 
     type Err = AcvpError;
 }
 
+impl DrbgMode {
+    /// Highest security strength in bits supported by the mode as per
+    /// SP800-57 Part 1.
+    pub fn security_strength(&self) -> usize {
+        match self {
+            Self::TDES => 112,
+            Self::SHA1 | Self::AES128 => 128,
+            Self::SHA224 | Self::SHA512_224 | Self::SHA3_224 | Self::AES192 => 192,
+            Self::SHA256
+            | Self::SHA384
+            | Self::SHA512
+            | Self::SHA512_256
+            | Self::SHA3_256
+            | Self::SHA3_384
+            | Self::SHA3_512
+            | Self::AES256 => 256,
+            Self::Nil => 0,
+        }
+    }
+
+    /// Output block length in bits, i.e. the digest size for Hash_DRBG and
+    /// HMAC_DRBG and the cipher block size for CTR_DRBG.
+    pub fn out_len(&self) -> usize {
+        match self {
+            Self::TDES => 64,
+            Self::AES128 | Self::AES192 | Self::AES256 => 128,
+            Self::SHA1 => 160,
+            Self::SHA224 | Self::SHA512_224 | Self::SHA3_224 => 224,
+            Self::SHA256 | Self::SHA512_256 | Self::SHA3_256 => 256,
+            Self::SHA384 | Self::SHA3_384 => 384,
+            Self::SHA512 | Self::SHA3_512 => 512,
+            Self::Nil => 0,
+        }
+    }
+
+    /// Seed length in bits for Hash_DRBG and CTR_DRBG (SP800-90A tables 2
+    /// and 3). For CTR_DRBG this is the key length plus the block length.
+    /// HMAC_DRBG has no seed length of its own.
+    pub fn seed_len(&self) -> usize {
+        match self {
+            Self::SHA1
+            | Self::SHA224
+            | Self::SHA256
+            | Self::SHA512_224
+            | Self::SHA512_256
+            | Self::SHA3_224
+            | Self::SHA3_256 => 440,
+            Self::SHA384 | Self::SHA512 | Self::SHA3_384 | Self::SHA3_512 => 888,
+            Self::TDES => 232,
+            Self::AES128 => 256,
+            Self::AES192 => 320,
+            Self::AES256 => 384,
+            Self::Nil => 0,
+        }
+    }
+}
+
+/// Intended use of an additional DRBG call following the instantiation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrbgIntendedUse {
+    Generate,
+    ReSeed,
+}
+
+impl std::str::FromStr for DrbgIntendedUse {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "generate" => Ok(Self::Generate),
+            "reSeed" => Ok(Self::ReSeed),
+            _ => Err(AcvpError::invalid(format!(
+                "Intended use '{}' is not valid",
+                s
+            ))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+/// Fetch an optional hex value. Absent keys and empty strings both map to
+/// `None` since the vector sets use either to express a null input.
+fn get_opt_hex(key: &str, json: &JsonValue) -> AcvpResult<Option<Vec<u8>>> {
+    if !json.has_key(key) {
+        return Ok(None);
+    }
+    let hexstr = crate::util::get_acvp_str(key, json)?;
+    if hexstr.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(crate::util::hex2bin(&hexstr)?))
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DrbgOtherInput {
-    pub iuse: String,
+    pub iuse: DrbgIntendedUse,
     pub addtl_input: Vec<u8>,
-    pub entropy_input: Vec<u8>,
+    pub entropy_input: Option<Vec<u8>>,
 }
 
 impl DrbgOtherInput {
     pub fn new(testjson: &JsonValue) -> AcvpResult<Self> {
-        let iuse = crate::util::get_acvp_str("intendedUse", testjson)?;
-        let addtlhex = crate::util::get_acvp_str("additionalInput", testjson)?;
-        let addtl_input = crate::util::hex2bin(&addtlhex)?;
+        let iuse: DrbgIntendedUse = crate::util::get_acvp_str("intendedUse", testjson)?.parse()?;
 
-        let eihex = crate::util::get_acvp_str("entropyInput", testjson)?;
-        let entropy_input = crate::util::hex2bin(&eihex)?;
+        let mut addtl_input = Vec::new();
+        if testjson.has_key("additionalInput") {
+            let addtlhex = crate::util::get_acvp_str("additionalInput", testjson)?;
+            addtl_input = crate::util::hex2bin(&addtlhex)?;
+        }
+
+        // Entropy is only provided for reseeding, either explicitly or
+        // implicitly before every generate call with prediction resistance.
+        let entropy_input = get_opt_hex("entropyInput", testjson)?;
+        if iuse == DrbgIntendedUse::ReSeed && entropy_input.is_none() {
+            return Err(AcvpError::invalid("DRBG reseed requires 'entropyInput'"));
+        }
 
         Ok(DrbgOtherInput {
             iuse,
@@ -69,51 +185,82 @@ impl DrbgOtherInput {
     }
 }
 
+/// DRBG test group parameters, `returned_bits_len` is in bytes.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DrbgGroupData {
+    pub algorithm: AcvpAlgorithm,
+    pub test_type: TestType,
+    pub mode: DrbgMode,
+    pub der_func: bool,
+    pub prediction_resistance: bool,
+    pub reseed: bool,
+    pub returned_bits_len: usize,
+}
+
+impl GroupData for DrbgGroupData {
+    fn new(algorithm: AcvpAlgorithm, test_type: TestType, tg: &JsonValue) -> AcvpResult<Self> {
+        let mode: DrbgMode = crate::util::get_acvp_str("mode", tg)?.parse()?;
+
+        // The derivation function is a CTR_DRBG option only.
+        let mut der_func = false;
+        if algorithm == AcvpAlgorithm::Rng(DrbgType::Ctr) || tg.has_key("derFunc") {
+            der_func = crate::util::get_acvp_bool("derFunc", tg)?;
+        }
+
+        let prediction_resistance = crate::util::get_acvp_bool("predResistance", tg)?;
+        let reseed = crate::util::get_acvp_bool("reSeed", tg)?;
+        let returned_bits_len = crate::util::get_acvp_u32("returnedBitsLen", tg)? as usize / 8;
+
+        Ok(DrbgGroupData {
+            algorithm,
+            test_type,
+            mode,
+            der_func,
+            prediction_resistance,
+            reseed,
+            returned_bits_len,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Drbg {
-    algorithm: String,
+    algorithm: AcvpAlgorithm,
     tcid: u32,
     test_type: TestType,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::json_value"))]
     res_json: JsonValue,
+    pub mode: DrbgMode,
+    pub der_func: bool,
+    pub prediction_resistance: bool,
+    pub reseed: bool,
+    pub returned_bits_len: usize,
     pub entropy_input: Vec<u8>,
-    pub nonce: Vec<u8>,
-    pub perso_string: Vec<u8>,
+    pub nonce: Option<Vec<u8>>,
+    pub perso_string: Option<Vec<u8>>,
     pub other_input: Vec<DrbgOtherInput>,
 }
 
 impl TestCase for Drbg {
-    fn new(test: &str, tgdata: &crate::parser::TestGroupData) -> AcvpResult<Self>
-    where
-        Self: Sized,
-    {
-        let testjson = match json::parse(test) {
-            Ok(t) => t,
-            Err(_e) => {
-                return Err(AcvpError {
-                    code: -libc::EINVAL,
-                    message: String::from("Failed to parse JSON data for DRBG"),
-                });
-            }
-        };
+    type GroupData = DrbgGroupData;
 
-        let tcid = crate::util::get_acvp_u32("tcId", &testjson)?;
+    fn new(testjson: &JsonValue, tgdata: &DrbgGroupData) -> AcvpResult<Self> {
+        let tcid = crate::util::get_acvp_u32("tcId", testjson)?;
 
-        let eihex = crate::util::get_acvp_str("entropyInput", &testjson)?;
+        let eihex = crate::util::get_acvp_str("entropyInput", testjson)?;
         let entropy_input = crate::util::hex2bin(&eihex)?;
 
-        let noncehex = crate::util::get_acvp_str("nonce", &testjson)?;
-        let nonce = crate::util::hex2bin(&noncehex)?;
-
-        let pshex = crate::util::get_acvp_str("persoString", &testjson)?;
-        let perso_string = crate::util::hex2bin(&pshex)?;
+        let nonce = get_opt_hex("nonce", testjson)?;
+        let perso_string = get_opt_hex("persoString", testjson)?;
 
         let oi = &testjson["otherInput"];
 
         if !oi.is_array() {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: String::from("other input for DRBG vector is not an array"),
-            });
+            return Err(AcvpError::parse(
+                "other input for DRBG vector is not an array",
+            ));
         }
 
         let mut other_input = Vec::new();
@@ -122,10 +269,15 @@ impl TestCase for Drbg {
         }
 
         Ok(Drbg {
-            algorithm: tgdata.algorithm.to_string(),
+            algorithm: tgdata.algorithm,
             tcid,
             test_type: tgdata.test_type,
             res_json: JsonValue::new_object(),
+            mode: tgdata.mode,
+            der_func: tgdata.der_func,
+            prediction_resistance: tgdata.prediction_resistance,
+            reseed: tgdata.reseed,
+            returned_bits_len: tgdata.returned_bits_len,
             entropy_input,
             nonce,
             perso_string,
@@ -135,33 +287,37 @@ impl TestCase for Drbg {
 
     fn get_result(&self) -> AcvpResult<JsonValue> {
         if self.res_json.is_empty() {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "The result is not yet set, call set_*_result APIs".to_string(),
-            });
+            return Err(AcvpError::result_not_set());
         }
         Ok(self.res_json.clone())
     }
 
     fn dump_result(&self) -> AcvpResult<String> {
         if self.res_json.is_empty() {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "The result is not yet set, call set_*_result APIs".to_string(),
-            });
+            return Err(AcvpError::result_not_set());
         }
         Ok(self.res_json.dump())
     }
 
     fn pretty_result(&self) -> AcvpResult<String> {
         if self.res_json.is_empty() {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "The result is not yet set, call set_*_result APIs".to_string(),
-            });
+            return Err(AcvpError::result_not_set());
         }
         Ok(self.res_json.pretty(3))
     }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        match res {
+            AnyResult::Bytes(res) => self.set_result(res),
+            res => Err(res.unsupported()),
+        }
+    }
+
+    fn validate_result(&self, res: &JsonValue) -> Vec<String> {
+        let mut out = Vec::new();
+        crate::validate::check_hex_len(res, "returnedBits", self.returned_bits_len, &mut out);
+        out
+    }
 }
 
 impl TestResult<Vec<u8>> for Drbg {
@@ -174,3 +330,92 @@ impl TestResult<Vec<u8>> for Drbg {
         Ok(())
     }
 }
+
+/// SP800-90A DRBG implementation under test, driven by `Drbg::execute`.
+pub trait DrbgInstance {
+    /// Instantiate the DRBG. The DRBG flavour and its configuration (mode,
+    /// derivation function, prediction resistance) are taken from `test`.
+    fn instantiate(
+        &mut self,
+        test: &Drbg,
+        entropy_input: &[u8],
+        nonce: Option<&[u8]>,
+        perso_string: Option<&[u8]>,
+    ) -> AcvpResult<()>;
+
+    fn reseed(&mut self, entropy_input: &[u8], addtl_input: &[u8]) -> AcvpResult<()>;
+
+    /// Generate `out_len` bytes. With prediction resistance enabled,
+    /// `entropy_input` carries the fresh entropy for the reseed which has to
+    /// precede the generation, otherwise it is `None`.
+    fn generate(
+        &mut self,
+        out_len: usize,
+        addtl_input: &[u8],
+        entropy_input: Option<&[u8]>,
+    ) -> AcvpResult<Vec<u8>>;
+
+    fn uninstantiate(&mut self) {}
+}
+
+impl Drbg {
+    pub fn drbg_type(&self) -> AcvpResult<DrbgType> {
+        match self.algorithm {
+            AcvpAlgorithm::Rng(drbg_type) => Ok(drbg_type),
+            _ => Err(AcvpError::invalid("Algorithm is not a DRBG")),
+        }
+    }
+
+    /// Run the test's call sequence against `drbg`: instantiate, then reseed
+    /// or generate for every other input. The output of the last generate
+    /// call is set as the result and returned.
+    pub fn execute<D: DrbgInstance + ?Sized>(&mut self, drbg: &mut D) -> AcvpResult<Vec<u8>> {
+        drbg.instantiate(
+            self,
+            &self.entropy_input,
+            self.nonce.as_deref(),
+            self.perso_string.as_deref(),
+        )?;
+
+        let mut out = Vec::new();
+        for inp in &self.other_input {
+            let res = match inp.iuse {
+                DrbgIntendedUse::ReSeed => {
+                    let entropy = inp.entropy_input.as_deref().unwrap_or_default();
+                    drbg.reseed(entropy, &inp.addtl_input)
+                }
+                DrbgIntendedUse::Generate => {
+                    let mut entropy = None;
+                    if self.prediction_resistance {
+                        entropy = match inp.entropy_input.as_deref() {
+                            Some(ei) => Some(ei),
+                            None => {
+                                drbg.uninstantiate();
+                                return Err(AcvpError::invalid(
+                                    "Prediction resistance requires 'entropyInput'",
+                                ));
+                            }
+                        };
+                    }
+                    drbg.generate(self.returned_bits_len, &inp.addtl_input, entropy)
+                        .map(|o| out = o)
+                }
+            };
+            if let Err(e) = res {
+                drbg.uninstantiate();
+                return Err(e);
+            }
+        }
+        drbg.uninstantiate();
+
+        self.set_result(out.clone())?;
+        Ok(out)
+    }
+}
+
+impl BackendTest for Drbg {
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        let mut drbg = backend.drbg(self.drbg_type()?)?;
+        self.execute(drbg.as_mut()).map(|_| ())
+    }
+}
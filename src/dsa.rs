@@ -0,0 +1,530 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use json::JsonValue;
+
+use crate::{
+    backend::{Backend, BackendTest},
+    dispatch::AnyResult,
+    parser::{GroupData, TestCase, TestResult},
+    util::{AcvpAlgorithm, HashAlg, TestType},
+    AcvpError, AcvpResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DsaMode {
+    PqgGen,
+    PqgVer,
+    KeyGen,
+    SigGen,
+    SigVer,
+}
+
+impl std::str::FromStr for DsaMode {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pqgGen" => Ok(Self::PqgGen),
+            "pqgVer" => Ok(Self::PqgVer),
+            "keyGen" => Ok(Self::KeyGen),
+            "sigGen" => Ok(Self::SigGen),
+            "sigVer" => Ok(Self::SigVer),
+            _ => Err(AcvpError::unsupported(format!(
+                "DSA mode '{}' is not valid",
+                s
+            ))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+/// Method used to generate (or verify) the domain parameters p and q.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PQMode {
+    Probable,
+    Provable,
+    Nil,
+}
+
+impl std::str::FromStr for PQMode {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "probable" => Ok(Self::Probable),
+            "provable" => Ok(Self::Provable),
+            _ => Err(AcvpError::invalid(format!("pqMode '{}' is not valid", s))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+/// Method used to generate (or verify) the generator g.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GMode {
+    Unverifiable,
+    Canonical,
+    Nil,
+}
+
+impl std::str::FromStr for GMode {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unverifiable" => Ok(Self::Unverifiable),
+            "canonical" => Ok(Self::Canonical),
+            _ => Err(AcvpError::invalid(format!("gMode '{}' is not valid", s))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+/// Result of a pqgGen test.
+///
+/// For pq generation the IUT returns p, q and the domain parameter seed along
+/// with either `counter` (probable) or `p_counter` and `q_counter` (provable).
+/// For g generation only `g` is returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DsaPQGOutput {
+    pub p: Vec<u8>,
+    pub q: Vec<u8>,
+    pub g: Vec<u8>,
+    pub domain_seed: Vec<u8>,
+    pub counter: u32,
+    pub p_counter: u32,
+    pub q_counter: u32,
+}
+
+impl DsaPQGOutput {
+    pub fn new_probable(p: Vec<u8>, q: Vec<u8>, domain_seed: Vec<u8>, counter: u32) -> Self {
+        DsaPQGOutput {
+            p,
+            q,
+            g: Vec::new(),
+            domain_seed,
+            counter,
+            p_counter: 0,
+            q_counter: 0,
+        }
+    }
+
+    pub fn new_provable(
+        p: Vec<u8>,
+        q: Vec<u8>,
+        domain_seed: Vec<u8>,
+        p_counter: u32,
+        q_counter: u32,
+    ) -> Self {
+        DsaPQGOutput {
+            p,
+            q,
+            g: Vec::new(),
+            domain_seed,
+            counter: 0,
+            p_counter,
+            q_counter,
+        }
+    }
+
+    pub fn new_g(g: Vec<u8>) -> Self {
+        DsaPQGOutput {
+            p: Vec::new(),
+            q: Vec::new(),
+            g,
+            domain_seed: Vec::new(),
+            counter: 0,
+            p_counter: 0,
+            q_counter: 0,
+        }
+    }
+}
+
+/// Result of a keyGen test. The domain parameters are reported once per
+/// test group, the key pair for every test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DsaKeyOutput {
+    pub p: Vec<u8>,
+    pub q: Vec<u8>,
+    pub g: Vec<u8>,
+    pub x: Vec<u8>,
+    pub y: Vec<u8>,
+}
+
+impl DsaKeyOutput {
+    pub fn new(p: Vec<u8>, q: Vec<u8>, g: Vec<u8>, x: Vec<u8>, y: Vec<u8>) -> Self {
+        DsaKeyOutput { p, q, g, x, y }
+    }
+}
+
+/// Result of a sigGen test. The domain parameters and public key are
+/// reported once per test group, the signature for every test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DsaSigOutput {
+    pub p: Vec<u8>,
+    pub q: Vec<u8>,
+    pub g: Vec<u8>,
+    pub y: Vec<u8>,
+    pub r: Vec<u8>,
+    pub s: Vec<u8>,
+}
+
+impl DsaSigOutput {
+    pub fn new(p: Vec<u8>, q: Vec<u8>, g: Vec<u8>, y: Vec<u8>, r: Vec<u8>, s: Vec<u8>) -> Self {
+        DsaSigOutput { p, q, g, y, r, s }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DsaGroupData {
+    pub algorithm: AcvpAlgorithm,
+    pub test_type: TestType,
+    pub mode: DsaMode,
+    pub l: usize,
+    pub n: usize,
+    pub hash_alg: Option<HashAlg>,
+    pub pqmode: PQMode,
+    pub gmode: GMode,
+    pub p: Vec<u8>,
+    pub q: Vec<u8>,
+    pub g: Vec<u8>,
+}
+
+impl GroupData for DsaGroupData {
+    fn new(algorithm: AcvpAlgorithm, test_type: TestType, tg: &JsonValue) -> AcvpResult<Self> {
+        let mode = match algorithm {
+            AcvpAlgorithm::Dsa(mode) => mode,
+            _ => {
+                return Err(AcvpError::invalid("Algorithm is not DSA"));
+            }
+        };
+
+        let l = crate::util::get_acvp_u32("l", tg)? as usize;
+        let n = crate::util::get_acvp_u32("n", tg)? as usize;
+
+        // Key generation is the only mode which does not involve a hash.
+        let mut hash_alg = None;
+        if mode != DsaMode::KeyGen {
+            hash_alg = Some(crate::util::get_acvp_str("hashAlg", tg)?.parse()?);
+        }
+
+        // pqgGen and pqgVer groups either test the p and q or the g
+        // generation.
+        let mut pqmode = PQMode::Nil;
+        let mut gmode = GMode::Nil;
+        if tg.has_key("pqMode") {
+            pqmode = crate::util::get_acvp_str("pqMode", tg)?.parse()?;
+        } else if tg.has_key("gMode") {
+            gmode = crate::util::get_acvp_str("gMode", tg)?.parse()?;
+        } else if mode == DsaMode::PqgGen || mode == DsaMode::PqgVer {
+            return Err(AcvpError::invalid(
+                "Either 'pqMode' or 'gMode' is required for DSA pqg tests",
+            ));
+        }
+
+        let mut p = Vec::new();
+        let mut q = Vec::new();
+        let mut g = Vec::new();
+        if mode == DsaMode::SigVer {
+            p = crate::util::get_acvp_mpi("p", tg)?;
+            q = crate::util::get_acvp_mpi("q", tg)?;
+            g = crate::util::get_acvp_mpi("g", tg)?;
+        }
+
+        Ok(DsaGroupData {
+            algorithm,
+            test_type,
+            mode,
+            l,
+            n,
+            hash_alg,
+            pqmode,
+            gmode,
+            p,
+            q,
+            g,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dsa {
+    algorithm: AcvpAlgorithm,
+    tcid: u32,
+    test_type: TestType,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::json_value"))]
+    res_json: JsonValue,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::json_value"))]
+    grp_res_json: JsonValue,
+    pub mode: DsaMode,
+    pub l: usize,
+    pub n: usize,
+    pub hash_alg: Option<HashAlg>,
+    pub pqmode: PQMode,
+    pub gmode: GMode,
+    pub p: Vec<u8>,
+    pub q: Vec<u8>,
+    pub g: Vec<u8>,
+    pub domain_seed: Vec<u8>,
+    pub counter: u32,
+    pub p_counter: u32,
+    pub q_counter: u32,
+    pub h: Vec<u8>,
+    pub index: Vec<u8>,
+    pub msg: Vec<u8>,
+    pub y: Vec<u8>,
+    pub r: Vec<u8>,
+    pub s: Vec<u8>,
+}
+
+fn get_opt_hex(key: &str, test: &JsonValue) -> AcvpResult<Vec<u8>> {
+    if !test.has_key(key) {
+        return Ok(Vec::new());
+    }
+    let hexstr = crate::util::get_acvp_str(key, test)?;
+    crate::util::hex2bin(&hexstr)
+}
+
+fn get_opt_mpi(key: &str, test: &JsonValue) -> AcvpResult<Vec<u8>> {
+    if !test.has_key(key) {
+        return Ok(Vec::new());
+    }
+    crate::util::get_acvp_mpi(key, test)
+}
+
+fn get_opt_u32(key: &str, test: &JsonValue) -> AcvpResult<u32> {
+    if !test.has_key(key) {
+        return Ok(0);
+    }
+    crate::util::get_acvp_u32(key, test)
+}
+
+impl TestCase for Dsa {
+    type GroupData = DsaGroupData;
+
+    fn new(test: &JsonValue, tgdata: &DsaGroupData) -> AcvpResult<Self> {
+        let tcid = crate::util::get_acvp_u32("tcId", test)?;
+        let mode = tgdata.mode;
+
+        // The domain parameters are provided per group for sigVer, and per
+        // test for pqgVer and g generation.
+        let mut p = get_opt_mpi("p", test)?;
+        if p.is_empty() {
+            p = tgdata.p.clone();
+        }
+        let mut q = get_opt_mpi("q", test)?;
+        if q.is_empty() {
+            q = tgdata.q.clone();
+        }
+        let mut g = get_opt_mpi("g", test)?;
+        if g.is_empty() {
+            g = tgdata.g.clone();
+        }
+
+        let mut msg = Vec::new();
+        if mode == DsaMode::SigGen || mode == DsaMode::SigVer {
+            let msghex = crate::util::get_acvp_str("message", test)?;
+            msg = crate::util::hex2bin(&msghex)?;
+        }
+
+        let mut y = Vec::new();
+        let mut r = Vec::new();
+        let mut s = Vec::new();
+        if mode == DsaMode::SigVer {
+            y = crate::util::get_acvp_mpi("y", test)?;
+            r = crate::util::get_acvp_mpi("r", test)?;
+            s = crate::util::get_acvp_mpi("s", test)?;
+        }
+
+        Ok(Dsa {
+            algorithm: tgdata.algorithm,
+            tcid,
+            test_type: tgdata.test_type,
+            res_json: JsonValue::new_object(),
+            grp_res_json: JsonValue::new_object(),
+            mode,
+            l: tgdata.l,
+            n: tgdata.n,
+            hash_alg: tgdata.hash_alg,
+            pqmode: tgdata.pqmode,
+            gmode: tgdata.gmode,
+            p,
+            q,
+            g,
+            domain_seed: get_opt_hex("domainSeed", test)?,
+            counter: get_opt_u32("counter", test)?,
+            p_counter: get_opt_u32("pCounter", test)?,
+            q_counter: get_opt_u32("qCounter", test)?,
+            h: get_opt_hex("h", test)?,
+            index: get_opt_hex("index", test)?,
+            msg,
+            y,
+            r,
+            s,
+        })
+    }
+
+    fn get_result(&self) -> AcvpResult<JsonValue> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.clone())
+    }
+
+    fn get_group_result(&self) -> Option<JsonValue> {
+        if self.grp_res_json.is_empty() {
+            return None;
+        }
+        Some(self.grp_res_json.clone())
+    }
+
+    fn dump_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.dump())
+    }
+
+    fn pretty_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.pretty(3))
+    }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        match res {
+            AnyResult::Bool(res) => self.set_result(res),
+            AnyResult::DsaPqg(res) => self.set_result(res),
+            AnyResult::DsaKey(res) => self.set_result(res),
+            AnyResult::DsaSig(res) => self.set_result(res),
+            res => Err(res.unsupported()),
+        }
+    }
+}
+
+impl Dsa {
+    fn check_mode(&self, mode: DsaMode) -> AcvpResult<()> {
+        if self.mode != mode {
+            return Err(AcvpError::invalid_result(format!(
+                "Result type is not valid for DSA mode {:?}",
+                self.mode
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl TestResult<DsaPQGOutput> for Dsa {
+    fn set_result(&mut self, result: DsaPQGOutput) -> AcvpResult<()> {
+        self.check_mode(DsaMode::PqgGen)?;
+        let mut res = JsonValue::new_object();
+        res["tcId"] = self.tcid.into();
+        match self.pqmode {
+            PQMode::Probable => {
+                res["p"] = crate::util::mpi2hex(&result.p).into();
+                res["q"] = crate::util::mpi2hex(&result.q).into();
+                res["domainSeed"] = hex::encode(result.domain_seed).to_ascii_uppercase().into();
+                res["counter"] = result.counter.into();
+            }
+            PQMode::Provable => {
+                res["p"] = crate::util::mpi2hex(&result.p).into();
+                res["q"] = crate::util::mpi2hex(&result.q).into();
+                res["domainSeed"] = hex::encode(result.domain_seed).to_ascii_uppercase().into();
+                res["pCounter"] = result.p_counter.into();
+                res["qCounter"] = result.q_counter.into();
+            }
+            PQMode::Nil => {
+                res["g"] = crate::util::mpi2hex(&result.g).into();
+            }
+        }
+        self.res_json = res;
+        Ok(())
+    }
+}
+
+impl TestResult<DsaKeyOutput> for Dsa {
+    fn set_result(&mut self, result: DsaKeyOutput) -> AcvpResult<()> {
+        self.check_mode(DsaMode::KeyGen)?;
+        self.grp_res_json = json::object! {
+            p: crate::util::mpi2hex(&result.p),
+            q: crate::util::mpi2hex(&result.q),
+            g: crate::util::mpi2hex(&result.g),
+        };
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            x: crate::util::mpi2hex(&result.x),
+            y: crate::util::mpi2hex(&result.y),
+        };
+        Ok(())
+    }
+}
+
+impl TestResult<DsaSigOutput> for Dsa {
+    fn set_result(&mut self, result: DsaSigOutput) -> AcvpResult<()> {
+        self.check_mode(DsaMode::SigGen)?;
+        self.grp_res_json = json::object! {
+            p: crate::util::mpi2hex(&result.p),
+            q: crate::util::mpi2hex(&result.q),
+            g: crate::util::mpi2hex(&result.g),
+            y: crate::util::mpi2hex(&result.y),
+        };
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            r: crate::util::mpi2hex(&result.r),
+            s: crate::util::mpi2hex(&result.s),
+        };
+        Ok(())
+    }
+}
+
+impl TestResult<bool> for Dsa {
+    fn set_result(&mut self, result: bool) -> AcvpResult<()> {
+        if self.mode != DsaMode::PqgVer && self.mode != DsaMode::SigVer {
+            return Err(AcvpError::invalid_result(format!(
+                "Result type is not valid for DSA mode {:?}",
+                self.mode
+            )));
+        }
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            testPassed: result,
+        };
+        Ok(())
+    }
+}
+
+impl BackendTest for Dsa {
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        match self.mode {
+            DsaMode::PqgGen => {
+                let pqg = backend.dsa_pqg_gen(self)?;
+                self.set_result(pqg)
+            }
+            DsaMode::PqgVer => {
+                let ok = backend.dsa_pqg_ver(self)?;
+                self.set_result(ok)
+            }
+            DsaMode::KeyGen => {
+                let key = backend.dsa_key_gen(self)?;
+                self.set_result(key)
+            }
+            DsaMode::SigGen => {
+                let sig = backend.dsa_sig_gen(self)?;
+                self.set_result(sig)
+            }
+            DsaMode::SigVer => {
+                let ok = backend.dsa_sig_ver(self)?;
+                self.set_result(ok)
+            }
+        }
+    }
+}
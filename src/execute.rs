@@ -0,0 +1,196 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Parallel execution of the tests of a request.
+//!
+//! The tests (or test groups) are handed out one at a time to a fixed set of
+//! scoped worker threads, and every result is set on the test it was
+//! computed for. Execution stops handing out tests after the first failure,
+//! the failure of the earliest test in request order is reported.
+//!
+//! The `_journaled` variants record every result in a `Journal` as soon as
+//! it is set, so a run interrupted by a failure or a crash can be resumed
+//! with `AcvpRequest::resume` without repeating the tests already done.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    backend::{Backend, BackendTest},
+    journal::Journal,
+    parser::{AcvpRequest, AcvpTest, AcvpTestGroup, TestCase, TestResult},
+    AcvpError, AcvpResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Executor {
+    threads: usize,
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Executor {
+    /// Execute with `threads` worker threads, 0 uses the available
+    /// parallelism of the host.
+    pub fn new(threads: usize) -> Self {
+        let threads = match threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        Executor { threads }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Run `work` on every item of `items` across the worker threads.
+    fn execute<I, W>(&self, items: I, work: W) -> AcvpResult<()>
+    where
+        I: Iterator + Send,
+        W: Fn(I::Item) -> AcvpResult<()> + Sync,
+    {
+        let queue = Mutex::new(items.enumerate());
+        let stop = AtomicBool::new(false);
+        let failure: Mutex<Option<(usize, AcvpError)>> = Mutex::new(None);
+
+        thread::scope(|s| {
+            for _ in 0..self.threads {
+                s.spawn(|| {
+                    while !stop.load(Ordering::Relaxed) {
+                        let next = queue.lock().unwrap_or_else(|e| e.into_inner()).next();
+                        let (i, item) = match next {
+                            Some(next) => next,
+                            None => break,
+                        };
+                        if let Err(e) = work(item) {
+                            stop.store(true, Ordering::Relaxed);
+                            let mut failure = failure.lock().unwrap_or_else(|e| e.into_inner());
+                            if failure.as_ref().is_none_or(|(j, _)| i < *j) {
+                                *failure = Some((i, e));
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        match failure.into_inner().unwrap_or_else(|e| e.into_inner()) {
+            Some((_, e)) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Compute the result of every test without a result with `f` and set
+    /// it on the test, so tests restored with `AcvpRequest::resume` are
+    /// skipped. `f` gets mutable access to the test, e.g. to generate an IV
+    /// with `MsgAuth::generate_iv`.
+    pub fn run<T, R, F>(&self, req: &mut AcvpRequest<T>, f: F) -> AcvpResult<()>
+    where
+        T: TestCase + Send,
+        T::GroupData: Send,
+        AcvpTest<T>: TestResult<R>,
+        F: Fn(&mut AcvpTest<T>) -> AcvpResult<R> + Sync,
+    {
+        let vsid = req.vsid;
+        self.execute(req.remaining_tests(), |(tgid, test)| {
+            let tcid = test.tcid;
+            f(test)
+                .and_then(|res| test.set_result(res))
+                .map_err(|e| e.with_vsid(vsid).with_tgid(tgid).with_tcid(tcid))
+        })
+    }
+
+    /// Run the tests as `run` does, and record every result in `journal`.
+    pub fn run_journaled<T, R, F>(
+        &self,
+        req: &mut AcvpRequest<T>,
+        journal: &mut Journal,
+        f: F,
+    ) -> AcvpResult<()>
+    where
+        T: TestCase + Send,
+        T::GroupData: Send,
+        AcvpTest<T>: TestResult<R>,
+        F: Fn(&mut AcvpTest<T>) -> AcvpResult<R> + Sync,
+    {
+        let vsid = req.vsid;
+        let journal = Mutex::new(journal);
+        self.execute(req.remaining_tests(), |(tgid, test)| {
+            let tcid = test.tcid;
+            f(test)
+                .and_then(|res| test.set_result(res))
+                .and_then(|_| {
+                    let mut journal = journal.lock().unwrap_or_else(|e| e.into_inner());
+                    journal.record(vsid, tgid, test)
+                })
+                .map_err(|e| e.with_vsid(vsid).with_tgid(tgid).with_tcid(tcid))
+        })
+    }
+
+    /// Execute every test without a result with `backend`, as
+    /// `AcvpRequest::run` does but across the worker threads.
+    pub fn run_backend<T, B>(&self, req: &mut AcvpRequest<T>, backend: &B) -> AcvpResult<()>
+    where
+        T: BackendTest + Send,
+        T::GroupData: Send,
+        B: Backend + Sync + ?Sized,
+    {
+        let vsid = req.vsid;
+        self.execute(req.remaining_tests(), |(tgid, test)| {
+            test.run(backend)
+                .map_err(|e| e.with_vsid(vsid).with_tgid(tgid))
+        })
+    }
+
+    /// Execute the tests as `run_backend` does, and record every result in
+    /// `journal`.
+    pub fn run_backend_journaled<T, B>(
+        &self,
+        req: &mut AcvpRequest<T>,
+        journal: &mut Journal,
+        backend: &B,
+    ) -> AcvpResult<()>
+    where
+        T: BackendTest + Send,
+        T::GroupData: Send,
+        B: Backend + Sync + ?Sized,
+    {
+        let vsid = req.vsid;
+        let journal = Mutex::new(journal);
+        self.execute(req.remaining_tests(), |(tgid, test)| {
+            test.run(backend)
+                .and_then(|_| {
+                    let mut journal = journal.lock().unwrap_or_else(|e| e.into_inner());
+                    journal.record(vsid, tgid, test)
+                })
+                .map_err(|e| e.with_vsid(vsid).with_tgid(tgid))
+        })
+    }
+
+    /// Run `f` on every test group, for algorithms whose tests of a group
+    /// must be executed in order or share state, e.g. a key generated once
+    /// per group. `f` sets the results of the tests itself.
+    pub fn run_groups<T, F>(&self, req: &mut AcvpRequest<T>, f: F) -> AcvpResult<()>
+    where
+        T: TestCase + Send,
+        T::GroupData: Send,
+        F: Fn(&mut AcvpTestGroup<T>) -> AcvpResult<()> + Sync,
+    {
+        let vsid = req.vsid;
+        self.execute(req.testgroups.iter_mut(), |tg| {
+            f(tg).map_err(|e| e.with_vsid(vsid).with_tgid(tg.tgid))
+        })
+    }
+}
@@ -6,74 +6,226 @@
 use json::JsonValue;
 
 use crate::{
-    parser::{TestCase, TestGroupData, TestResult},
-    util::TestType,
+    backend::{Backend, BackendTest},
+    dispatch::AnyResult,
+    parser::{GroupData, TestCase, TestResult},
+    util::{AcvpAlgorithm, HashAlg, TestType},
+    validate::MCT_ITERATIONS,
     AcvpError, AcvpResult,
 };
 
+/// Inner iterations of the hash Monte Carlo tests.
+const MCT_INNER: usize = 1000;
+
+/// `min_out_len` and `max_out_len` are the output length range in bits of
+/// the SHAKE Monte Carlo tests, 0 for other tests.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HashGroupData {
+    pub algorithm: AcvpAlgorithm,
+    pub test_type: TestType,
+    pub min_out_len: usize,
+    pub max_out_len: usize,
+}
+
+impl GroupData for HashGroupData {
+    fn new(algorithm: AcvpAlgorithm, test_type: TestType, tg: &JsonValue) -> AcvpResult<Self> {
+        let mut min_out_len = 0;
+        let mut max_out_len = 0;
+        if tg.has_key("minOutLen") || tg.has_key("maxOutLen") {
+            min_out_len = crate::util::get_acvp_u32("minOutLen", tg)? as usize;
+            max_out_len = crate::util::get_acvp_u32("maxOutLen", tg)? as usize;
+        }
+
+        Ok(HashGroupData {
+            algorithm,
+            test_type,
+            min_out_len,
+            max_out_len,
+        })
+    }
+}
+
+/// Expansion technique of the message of a large data test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExpansionTechnique {
+    Repeating,
+}
+
+impl std::str::FromStr for ExpansionTechnique {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "repeating" => Ok(Self::Repeating),
+            _ => Err(AcvpError::unsupported(format!(
+                "Expansion technique '{}' is not valid",
+                s
+            ))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+/// Message of a hash large data test, `content` expanded to `full_len`
+/// bits. Both lengths are in bits and whole bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LargeMsg {
+    pub content: Vec<u8>,
+    pub content_len: usize,
+    pub full_len: usize,
+    pub expansion: ExpansionTechnique,
+}
+
+impl LargeMsg {
+    fn new(msg: &JsonValue) -> AcvpResult<Self> {
+        let contenthex = crate::util::get_acvp_str("content", msg)?;
+        let content = crate::util::hex2bin(&contenthex)?;
+        let content_len = crate::util::get_acvp_u32("contentLength", msg)? as usize;
+        let full_len = crate::util::get_acvp_u64("fullLength", msg)? as usize;
+        let expansion = crate::util::get_acvp_str("expansionTechnique", msg)?.parse()?;
+
+        if content_len == 0 || content_len % 8 != 0 || content_len != content.len() * 8 {
+            return Err(AcvpError::invalid(format!(
+                "Content of {} bytes does not match its length of {} bits",
+                content.len(),
+                content_len
+            )));
+        }
+        if full_len % 8 != 0 {
+            return Err(AcvpError::invalid(format!(
+                "Full length of {} bits is not a whole number of bytes",
+                full_len
+            )));
+        }
+
+        Ok(LargeMsg {
+            content,
+            content_len,
+            full_len,
+            expansion,
+        })
+    }
+
+    /// The expanded message. It is up to several GiB long, modules which
+    /// can hash a message in parts should rather hash `content` repeatedly,
+    /// `full_len / content_len` times followed by the remaining bytes.
+    pub fn expand(&self) -> Vec<u8> {
+        match self.expansion {
+            ExpansionTechnique::Repeating => self
+                .content
+                .iter()
+                .copied()
+                .cycle()
+                .take(self.full_len / 8)
+                .collect(),
+        }
+    }
+}
+
+/// Hash test case. `out_len` is the requested output length in bits of the
+/// SHAKE tests, taken from `outLen` (or `outputLen`), 0 for fixed length
+/// hashes. The output length range of the SHAKE Monte Carlo tests is copied
+/// from the group. Large data tests carry their message in `large_msg`, with
+/// an empty `msg`.
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SecureHash {
-    algorithm: String,
+    algorithm: AcvpAlgorithm,
     tcid: u32,
     test_type: TestType,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::json_value"))]
     res_json: JsonValue,
     pub msg: Vec<u8>,
+    pub out_len: usize,
+    pub min_out_len: usize,
+    pub max_out_len: usize,
+    pub large_msg: Option<LargeMsg>,
 }
 
 impl TestCase for SecureHash {
-    fn new(testjson: &str, tgdata: &TestGroupData) -> AcvpResult<Self> {
-        let test = match json::parse(testjson) {
-            Ok(test) => test,
-            Err(_e) => {
-                return Err(AcvpError {
-                    code: -libc::EINVAL,
-                    message: "Failed to parse testcase JSON for SHash".to_string(),
-                });
-            }
-        };
-        let tcid = crate::util::get_acvp_u32("tcId", &test)?;
+    type GroupData = HashGroupData;
+
+    fn new(test: &JsonValue, tgdata: &HashGroupData) -> AcvpResult<Self> {
+        let tcid = crate::util::get_acvp_u32("tcId", test)?;
+
+        let mut msg = Vec::new();
+        let mut large_msg = None;
+        if test.has_key("largeMsg") {
+            large_msg = Some(LargeMsg::new(&test["largeMsg"]).map_err(|e| e.at("largeMsg"))?);
+        } else {
+            let msghex = crate::util::get_acvp_str("msg", test)?;
+            msg = crate::util::hex2bin(&msghex)?;
+        }
 
-        let msghex = crate::util::get_acvp_str("msg", &test)?;
-        let msg = crate::util::hex2bin(&msghex)?;
+        let mut out_len = 0;
+        if test.has_key("outLen") {
+            out_len = crate::util::get_acvp_u32("outLen", test)? as usize;
+        } else if test.has_key("outputLen") {
+            out_len = crate::util::get_acvp_u32("outputLen", test)? as usize;
+        }
 
         Ok(SecureHash {
-            algorithm: tgdata.algorithm.to_string(),
+            algorithm: tgdata.algorithm,
             tcid,
             test_type: tgdata.test_type,
             res_json: JsonValue::new_object(),
             msg,
+            out_len,
+            min_out_len: tgdata.min_out_len,
+            max_out_len: tgdata.max_out_len,
+            large_msg,
         })
     }
 
     fn get_result(&self) -> AcvpResult<JsonValue> {
         if self.res_json.is_empty() {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "The result is not yet set, call set_*_result APIs".to_string(),
-            });
+            return Err(AcvpError::result_not_set());
         }
         Ok(self.res_json.clone())
     }
 
     fn dump_result(&self) -> AcvpResult<String> {
         if self.res_json.is_empty() {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "The result is not yet set, call set_*_result APIs".to_string(),
-            });
+            return Err(AcvpError::result_not_set());
         }
         Ok(self.res_json.dump())
     }
 
     fn pretty_result(&self) -> AcvpResult<String> {
         if self.res_json.is_empty() {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "The result is not yet set, call set_*_result APIs".to_string(),
-            });
+            return Err(AcvpError::result_not_set());
         }
         Ok(self.res_json.pretty(3))
     }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        match res {
+            AnyResult::Bytes(res) => self.set_result(res),
+            AnyResult::BytesVec(res) => self.set_result(res),
+            res => Err(res.unsupported()),
+        }
+    }
+
+    fn validate_result(&self, res: &JsonValue) -> Vec<String> {
+        let mut out = Vec::new();
+        let digest_len = match self.algorithm {
+            AcvpAlgorithm::Hash(alg) if alg.is_xof() => self.out_len.div_ceil(8),
+            AcvpAlgorithm::Hash(alg) => alg.digest_len(),
+            _ => 0,
+        };
+        if self.test_type == TestType::MCT {
+            let entries = crate::validate::check_mct_results(res, MCT_ITERATIONS, &mut out);
+            // The output length of SHAKE varies between iterations.
+            if digest_len != 0 {
+                crate::validate::check_mct_hex_len(&entries, "md", digest_len, &mut out);
+            }
+        } else if digest_len != 0 {
+            crate::validate::check_hex_len(res, "md", digest_len, &mut out);
+        }
+        out
+    }
 }
 
 impl TestResult<Vec<u8>> for SecureHash {
@@ -99,17 +251,22 @@ impl SecureHash {
     }
 
     fn set_mct_result(&mut self, mdvec: Vec<Vec<u8>>) -> AcvpResult<()> {
+        let xof = matches!(self.algorithm, AcvpAlgorithm::Hash(alg) if alg.is_xof());
         let mut results = JsonValue::new_array();
         for md in mdvec {
-            let mdhex = hex::encode(md).to_ascii_uppercase();
-            let res = json::object! { md: mdhex };
+            let mdhex = hex::encode(&md).to_ascii_uppercase();
+            let mut res = json::object! { md: mdhex };
+            // The output length of SHAKE varies between iterations.
+            if xof {
+                res["outLen"] = (md.len() * 8).into();
+            }
             match results.push(res) {
                 Ok(()) => {}
                 Err(_e) => {
-                    return Err(AcvpError {
-                        code: -1,
-                        message: "Unexpected failure pushing to JsonValue array".to_string(),
-                    });
+                    return Err(AcvpError::with_code(
+                        -1,
+                        "Unexpected failure pushing to JsonValue array",
+                    ));
                 }
             };
         }
@@ -120,3 +277,160 @@ impl SecureHash {
         Ok(())
     }
 }
+
+/// Run the Monte Carlo test of `alg` from `seed` with `hash`, which computes
+/// a single digest, and return the digest of every outer iteration.
+///
+/// SHA-1 and SHA-2 hash the concatenation of the last three digests, SHA-3
+/// hashes the previous digest. SHAKE needs the output length, see
+/// `shake_mct`.
+pub fn mct<F>(alg: HashAlg, seed: &[u8], mut hash: F) -> AcvpResult<Vec<Vec<u8>>>
+where
+    F: FnMut(&[u8]) -> AcvpResult<Vec<u8>>,
+{
+    if alg.is_xof() {
+        return Err(AcvpError::unsupported(format!(
+            "Monte Carlo test of {:?} needs an output length, use shake_mct",
+            alg
+        )));
+    }
+
+    let mut seed = seed.to_vec();
+    let mut mds = Vec::with_capacity(MCT_ITERATIONS);
+    for _ in 0..MCT_ITERATIONS {
+        if alg.is_sha3() {
+            for _ in 0..MCT_INNER {
+                seed = hash(&seed)?;
+            }
+        } else {
+            let (mut md0, mut md1) = (seed.clone(), seed.clone());
+            for _ in 0..MCT_INNER {
+                let md = hash(&[&md0[..], &md1[..], &seed[..]].concat())?;
+                md0 = md1;
+                md1 = seed;
+                seed = md;
+            }
+        }
+        mds.push(seed.clone());
+    }
+    Ok(mds)
+}
+
+/// Run the SHAKE Monte Carlo test from `seed` with `xof`, which computes an
+/// output of the given length in bytes, and return the output of every
+/// outer iteration.
+///
+/// `min_out_len` and `max_out_len` are in bits. Every inner iteration takes
+/// the leftmost 128 bits of the previous output, zero padded, as message.
+/// The first output is `max_out_len` long, the length of the next one is
+/// picked in the range by the rightmost 16 bits of the output.
+pub fn shake_mct<F>(
+    seed: &[u8],
+    min_out_len: usize,
+    max_out_len: usize,
+    mut xof: F,
+) -> AcvpResult<Vec<Vec<u8>>>
+where
+    F: FnMut(&[u8], usize) -> AcvpResult<Vec<u8>>,
+{
+    let (min_bytes, max_bytes) = (min_out_len / 8, max_out_len / 8);
+    if min_bytes < 2 || min_bytes > max_bytes {
+        return Err(AcvpError::invalid(format!(
+            "Invalid SHAKE output length range {}-{}",
+            min_out_len, max_out_len
+        )));
+    }
+    let range = max_bytes - min_bytes + 1;
+
+    let mut out_len = max_bytes;
+    let mut output = seed.to_vec();
+    let mut outputs = Vec::with_capacity(MCT_ITERATIONS);
+    for _ in 0..MCT_ITERATIONS {
+        for _ in 0..MCT_INNER {
+            let mut msg = [0u8; 16];
+            let n = output.len().min(16);
+            msg[..n].copy_from_slice(&output[..n]);
+            output = xof(&msg, out_len)?;
+            if output.len() != out_len {
+                return Err(AcvpError::invalid_result(format!(
+                    "XOF returned {} bytes, expected {}",
+                    output.len(),
+                    out_len
+                )));
+            }
+            let tail = u16::from_be_bytes([output[out_len - 2], output[out_len - 1]]);
+            out_len = min_bytes + tail as usize % range;
+        }
+        outputs.push(output.clone());
+    }
+    Ok(outputs)
+}
+
+impl SecureHash {
+    /// Run the Monte Carlo test of this test case with `hash`, see `mct`.
+    pub fn mct<F>(&self, hash: F) -> AcvpResult<Vec<Vec<u8>>>
+    where
+        F: FnMut(&[u8]) -> AcvpResult<Vec<u8>>,
+    {
+        match self.algorithm {
+            AcvpAlgorithm::Hash(alg) => mct(alg, &self.msg, hash),
+            _ => Err(AcvpError::invalid("Algorithm is not a hash")),
+        }
+    }
+
+    /// Run the SHAKE Monte Carlo test of this test case with `xof`, see
+    /// `shake_mct`.
+    pub fn shake_mct<F>(&self, xof: F) -> AcvpResult<Vec<Vec<u8>>>
+    where
+        F: FnMut(&[u8], usize) -> AcvpResult<Vec<u8>>,
+    {
+        match self.algorithm {
+            AcvpAlgorithm::Hash(alg) if alg.is_xof() => {
+                shake_mct(&self.msg, self.min_out_len, self.max_out_len, xof)
+            }
+            _ => Err(AcvpError::invalid("Algorithm is not SHAKE")),
+        }
+    }
+}
+
+impl BackendTest for SecureHash {
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        let alg = match self.algorithm {
+            AcvpAlgorithm::Hash(alg) => alg,
+            _ => return Err(AcvpError::invalid("Algorithm is not a hash")),
+        };
+        match self.test_type {
+            TestType::AFT | TestType::VOT if alg.is_xof() => {
+                if self.out_len == 0 {
+                    return Err(AcvpError::invalid("SHAKE test has no output length"));
+                }
+                let md = backend.xof(alg, &self.msg, self.out_len.div_ceil(8))?;
+                self.set_result(md)
+            }
+            TestType::AFT => {
+                let md = backend.hash(alg, &self.msg)?;
+                self.set_result(md)
+            }
+            TestType::LDT => {
+                let large_msg = match &self.large_msg {
+                    Some(large_msg) => large_msg,
+                    None => return Err(AcvpError::missing_field("largeMsg", "object")),
+                };
+                let md = backend.hash_ldt(alg, large_msg)?;
+                self.set_result(md)
+            }
+            TestType::MCT if alg.is_xof() => {
+                let mds = backend.shake_mct(alg, &self.msg, self.min_out_len, self.max_out_len)?;
+                self.set_result(mds)
+            }
+            TestType::MCT => {
+                let mds = backend.hash_mct(alg, &self.msg)?;
+                self.set_result(mds)
+            }
+            test_type => Err(AcvpError::unsupported(format!(
+                "Test type {:?} is not supported for hashes",
+                test_type
+            ))),
+        }
+    }
+}
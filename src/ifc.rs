@@ -0,0 +1,376 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use json::JsonValue;
+
+use crate::{
+    backend::{Backend, BackendTest},
+    dispatch::AnyResult,
+    parser::{GroupData, TestCase, TestResult},
+    util::{AcvpAlgorithm, HashAlg, IfcAlg, TestType},
+    AcvpError, AcvpResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KasRole {
+    Initiator,
+    Responder,
+    Nil,
+}
+
+impl std::str::FromStr for KasRole {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "initiator" => Ok(Self::Initiator),
+            "responder" => Ok(Self::Responder),
+            _ => Err(AcvpError::invalid(format!("kasRole '{}' is not valid", s))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+/// Key confirmation role of the IUT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KcRole {
+    Provider,
+    Recipient,
+    Nil,
+}
+
+impl std::str::FromStr for KcRole {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "provider" => Ok(Self::Provider),
+            "recipient" => Ok(Self::Recipient),
+            _ => Err(AcvpError::invalid(format!(
+                "keyConfirmationRole '{}' is not valid",
+                s
+            ))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+/// Format of the RSA private key, either (n, e, d) or the CRT form
+/// (n, e, p, q, dmp1, dmq1, iqmp).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IfcKeyFormat {
+    Standard,
+    Crt,
+    Nil,
+}
+
+impl std::str::FromStr for IfcKeyFormat {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(Self::Standard),
+            "crt" => Ok(Self::Crt),
+            _ => Err(AcvpError::invalid(format!(
+                "keyFormat '{}' is not valid",
+                s
+            ))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+impl IfcKeyFormat {
+    /// Derive the key format from an SP800-56B `keyGenerationMethod` such as
+    /// "rsakpg1-crt" or "rsakpg2-basic".
+    pub fn from_keygen_method(method: &str) -> Self {
+        if method.ends_with("-crt") {
+            Self::Crt
+        } else {
+            Self::Standard
+        }
+    }
+}
+
+/// RSA key as provided in the IFC vector sets. Components which are not part
+/// of the key format in use are left empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IfcKey {
+    pub n: Vec<u8>,
+    pub e: Vec<u8>,
+    pub p: Vec<u8>,
+    pub q: Vec<u8>,
+    pub d: Vec<u8>,
+    pub dmp1: Vec<u8>,
+    pub dmq1: Vec<u8>,
+    pub iqmp: Vec<u8>,
+}
+
+fn get_opt_mpi(key: &str, json: &JsonValue) -> AcvpResult<Vec<u8>> {
+    if !json.has_key(key) {
+        return Ok(Vec::new());
+    }
+    crate::util::get_acvp_mpi(key, json)
+}
+
+fn get_opt_hex(key: &str, json: &JsonValue) -> AcvpResult<Vec<u8>> {
+    if !json.has_key(key) {
+        return Ok(Vec::new());
+    }
+    let hexstr = crate::util::get_acvp_str(key, json)?;
+    crate::util::hex2bin(&hexstr)
+}
+
+impl IfcKey {
+    /// Parse the key components carrying the given party prefix, e.g.
+    /// "iut" for `iutN`, `iutE`, `iutDmp1`, ... An empty prefix parses the
+    /// plain `n`, `e`, `dmp1`, ... fields used by the RSA vector sets.
+    pub fn new(prefix: &str, json: &JsonValue) -> AcvpResult<Self> {
+        let field = |name: &str| {
+            if prefix.is_empty() {
+                name.to_ascii_lowercase()
+            } else {
+                format!("{}{}", prefix, name)
+            }
+        };
+        Ok(IfcKey {
+            n: get_opt_mpi(&field("N"), json)?,
+            e: get_opt_mpi(&field("E"), json)?,
+            p: get_opt_mpi(&field("P"), json)?,
+            q: get_opt_mpi(&field("Q"), json)?,
+            d: get_opt_mpi(&field("D"), json)?,
+            dmp1: get_opt_mpi(&field("Dmp1"), json)?,
+            dmq1: get_opt_mpi(&field("Dmq1"), json)?,
+            iqmp: get_opt_mpi(&field("Iqmp"), json)?,
+        })
+    }
+}
+
+/// Result of a KAS-IFC-SSC or KTS-IFC AFT test.
+///
+/// `iut_c` is the ciphertext produced by the IUT (left empty when the IUT
+/// only decapsulates). `secret` is the shared secret Z (or its hash when the
+/// group specifies `hashFunctionZ`) for KAS-IFC-SSC and the derived keying
+/// material for KTS-IFC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IfcOutput {
+    pub iut_c: Vec<u8>,
+    pub secret: Vec<u8>,
+}
+
+impl IfcOutput {
+    pub fn new(iut_c: Vec<u8>, secret: Vec<u8>) -> Self {
+        IfcOutput { iut_c, secret }
+    }
+}
+
+/// KAS-IFC-SSC and KTS-IFC group parameters. `modulo` and `l` are in bits,
+/// `l` is only present for KTS-IFC.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IfcGroupData {
+    pub algorithm: AcvpAlgorithm,
+    pub test_type: TestType,
+    pub scheme: String,
+    pub kas_role: KasRole,
+    pub kc_role: KcRole,
+    pub key_format: IfcKeyFormat,
+    pub modulo: usize,
+    pub hash_alg: Option<HashAlg>,
+    pub l: usize,
+    pub fixed_pub_exp: Vec<u8>,
+}
+
+impl GroupData for IfcGroupData {
+    fn new(algorithm: AcvpAlgorithm, test_type: TestType, tg: &JsonValue) -> AcvpResult<Self> {
+        let scheme = crate::util::get_acvp_str("scheme", tg)?;
+        let kas_role: KasRole = crate::util::get_acvp_str("kasRole", tg)?.parse()?;
+
+        let mut kc_role = KcRole::Nil;
+        if tg.has_key("keyConfirmationRole") {
+            kc_role = crate::util::get_acvp_str("keyConfirmationRole", tg)?.parse()?;
+        } else if tg["keyConfirmationMethod"].has_key("keyConfirmationRole") {
+            let kcm = &tg["keyConfirmationMethod"];
+            kc_role = crate::util::get_acvp_str("keyConfirmationRole", kcm)?.parse()?;
+        }
+
+        let key_format = if tg.has_key("keyFormat") {
+            crate::util::get_acvp_str("keyFormat", tg)?.parse()?
+        } else {
+            let method = crate::util::get_acvp_str("keyGenerationMethod", tg)?;
+            IfcKeyFormat::from_keygen_method(&method)
+        };
+
+        let modulo = crate::util::get_acvp_u32("modulo", tg)? as usize;
+
+        let mut hash_alg = None;
+        let mut l = 0;
+        if algorithm == AcvpAlgorithm::Ifc(IfcAlg::Kts) {
+            let kts = &tg["ktsConfiguration"];
+            hash_alg = Some(crate::util::get_acvp_str("hashAlg", kts)?.parse()?);
+            l = crate::util::get_acvp_u32("l", tg)? as usize;
+        } else if tg.has_key("hashFunctionZ") {
+            hash_alg = Some(crate::util::get_acvp_str("hashFunctionZ", tg)?.parse()?);
+        }
+
+        let mut fixed_pub_exp = Vec::new();
+        if tg.has_key("fixedPubExp") {
+            fixed_pub_exp = crate::util::get_acvp_mpi("fixedPubExp", tg)?;
+        }
+
+        Ok(IfcGroupData {
+            algorithm,
+            test_type,
+            scheme,
+            kas_role,
+            kc_role,
+            key_format,
+            modulo,
+            hash_alg,
+            l,
+            fixed_pub_exp,
+        })
+    }
+}
+
+/// SP800-56B KAS-IFC-SSC and KTS-IFC test case.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ifc {
+    algorithm: AcvpAlgorithm,
+    tcid: u32,
+    test_type: TestType,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::json_value"))]
+    res_json: JsonValue,
+    pub scheme: String,
+    pub kas_role: KasRole,
+    pub kc_role: KcRole,
+    pub key_format: IfcKeyFormat,
+    pub modulo: usize,
+    pub hash_alg: Option<HashAlg>,
+    pub l: usize,
+    pub iut_key: IfcKey,
+    pub server_key: IfcKey,
+    pub server_c: Vec<u8>,
+    pub iut_c: Vec<u8>,
+    pub z: Vec<u8>,
+    pub hash_z: Vec<u8>,
+    pub dkm: Vec<u8>,
+}
+
+impl TestCase for Ifc {
+    type GroupData = IfcGroupData;
+
+    fn new(test: &JsonValue, tgdata: &IfcGroupData) -> AcvpResult<Self> {
+        let tcid = crate::util::get_acvp_u32("tcId", test)?;
+
+        let mut iut_key = IfcKey::new("iut", test)?;
+        if iut_key.e.is_empty() {
+            iut_key.e = tgdata.fixed_pub_exp.clone();
+        }
+        let server_key = IfcKey::new("server", test)?;
+
+        Ok(Ifc {
+            algorithm: tgdata.algorithm,
+            tcid,
+            test_type: tgdata.test_type,
+            res_json: JsonValue::new_object(),
+            scheme: tgdata.scheme.to_string(),
+            kas_role: tgdata.kas_role,
+            kc_role: tgdata.kc_role,
+            key_format: tgdata.key_format,
+            modulo: tgdata.modulo,
+            hash_alg: tgdata.hash_alg,
+            l: tgdata.l,
+            iut_key,
+            server_key,
+            server_c: get_opt_hex("serverC", test)?,
+            iut_c: get_opt_hex("iutC", test)?,
+            z: get_opt_hex("z", test)?,
+            hash_z: get_opt_hex("hashZ", test)?,
+            dkm: get_opt_hex("dkm", test)?,
+        })
+    }
+
+    fn get_result(&self) -> AcvpResult<JsonValue> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.clone())
+    }
+
+    fn dump_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.dump())
+    }
+
+    fn pretty_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.pretty(3))
+    }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        match res {
+            AnyResult::Bool(res) => self.set_result(res),
+            AnyResult::Ifc(res) => self.set_result(res),
+            res => Err(res.unsupported()),
+        }
+    }
+}
+
+impl TestResult<IfcOutput> for Ifc {
+    fn set_result(&mut self, result: IfcOutput) -> AcvpResult<()> {
+        let mut res = JsonValue::new_object();
+        res["tcId"] = self.tcid.into();
+        if !result.iut_c.is_empty() {
+            res["iutC"] = hex::encode(result.iut_c).to_ascii_uppercase().into();
+        }
+        let secret = hex::encode(result.secret).to_ascii_uppercase();
+        if self.algorithm == AcvpAlgorithm::Ifc(IfcAlg::Kts) {
+            res["dkm"] = secret.into();
+        } else if self.hash_alg.is_some() {
+            res["hashZ"] = secret.into();
+        } else {
+            res["z"] = secret.into();
+        }
+        self.res_json = res;
+        Ok(())
+    }
+}
+
+impl TestResult<bool> for Ifc {
+    fn set_result(&mut self, result: bool) -> AcvpResult<()> {
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            testPassed: result,
+        };
+        Ok(())
+    }
+}
+
+impl BackendTest for Ifc {
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        match self.test_type {
+            TestType::AFT => {
+                let out = backend.ifc(self)?;
+                self.set_result(out)
+            }
+            TestType::VAL => {
+                let ok = backend.ifc_verify(self)?;
+                self.set_result(ok)
+            }
+            test_type => Err(AcvpError::unsupported(format!(
+                "Test type {:?} is not supported for IFC",
+                test_type
+            ))),
+        }
+    }
+}
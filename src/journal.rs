@@ -0,0 +1,232 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Append-only journal of test results, so an interrupted run can be
+//! resumed without repeating the tests already done.
+//!
+//! The journal holds one JSON object per line, keyed by vsId, tgId and tcId,
+//! with the result of the test and the group level result fields, if any.
+//! Each entry is flushed to disk before `Journal::record` returns. A later
+//! entry for the same test supersedes the earlier ones. An entry left
+//! incomplete by a crash is ignored when the journal is read back and
+//! dropped when it is reopened.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+use json::JsonValue;
+
+use crate::{
+    parser::{AcvpRequest, AcvpTest, TestCase, TestResult},
+    AcvpError, AcvpResult,
+};
+
+/// A test result read back from a journal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub vsid: u32,
+    pub tgid: u32,
+    pub tcid: u32,
+    pub result: JsonValue,
+    pub group_result: Option<JsonValue>,
+}
+
+impl JournalEntry {
+    fn from_json(entry: &JsonValue) -> AcvpResult<Self> {
+        let vsid = crate::util::get_acvp_u32("vsId", entry)?;
+        let tgid = crate::util::get_acvp_u32("tgId", entry)?;
+        let tcid = crate::util::get_acvp_u32("tcId", entry)?;
+        if !entry["result"].is_object() {
+            return Err(AcvpError::missing_field("result", "object"));
+        }
+        let group_result = match &entry["groupResult"] {
+            JsonValue::Null => None,
+            res => Some(res.clone()),
+        };
+        Ok(JournalEntry {
+            vsid,
+            tgid,
+            tcid,
+            result: entry["result"].clone(),
+            group_result,
+        })
+    }
+}
+
+/// Journal file opened for appending.
+#[derive(Debug)]
+pub struct Journal {
+    file: File,
+}
+
+impl Journal {
+    /// Open the journal at `path` for appending, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> AcvpResult<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(AcvpError::io)?;
+
+        // Drop an entry left incomplete by a crash, so that it does not
+        // swallow the next one.
+        let len = file.metadata().map_err(AcvpError::io)?.len();
+        let mut reader = BufReader::new(&file);
+        let mut line = Vec::new();
+        let mut complete = 0;
+        loop {
+            line.clear();
+            let n = reader.read_until(b'\n', &mut line).map_err(AcvpError::io)?;
+            if n == 0 || !line.ends_with(b"\n") {
+                break;
+            }
+            complete += n as u64;
+        }
+        if complete < len {
+            file.set_len(complete).map_err(AcvpError::io)?;
+        }
+        Ok(Journal { file })
+    }
+
+    /// Append the result of `test`, which belongs to test group `tgid` of
+    /// vector set `vsid`.
+    pub fn record<T: TestCase>(
+        &mut self,
+        vsid: u32,
+        tgid: u32,
+        test: &AcvpTest<T>,
+    ) -> AcvpResult<()> {
+        let result = test
+            .get_result()
+            .map_err(|e| e.with_vsid(vsid).with_tgid(tgid))?;
+        let mut entry = json::object! {
+            vsId: vsid,
+            tgId: tgid,
+            tcId: test.tcid,
+            result: result,
+        };
+        if let Some(grp_res) = test.get_group_result() {
+            entry["groupResult"] = grp_res;
+        }
+
+        let mut line = entry.dump();
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .map_err(AcvpError::io)?;
+        self.file.sync_data().map_err(AcvpError::io)
+    }
+
+    /// Set the result of `test` and record it in the journal.
+    pub fn set_result<T, R>(
+        &mut self,
+        vsid: u32,
+        tgid: u32,
+        test: &mut AcvpTest<T>,
+        res: R,
+    ) -> AcvpResult<()>
+    where
+        T: TestCase,
+        AcvpTest<T>: TestResult<R>,
+    {
+        test.set_result(res)
+            .map_err(|e| e.with_vsid(vsid).with_tgid(tgid))?;
+        self.record(vsid, tgid, test)
+    }
+
+    /// Read all entries of the journal at `path`, in the order they were
+    /// recorded.
+    pub fn entries<P: AsRef<Path>>(path: P) -> AcvpResult<Vec<JournalEntry>> {
+        let file = File::open(path).map_err(AcvpError::io)?;
+        let mut lines = Vec::new();
+        for line in BufReader::new(file).lines() {
+            lines.push(line.map_err(AcvpError::io)?);
+        }
+
+        let mut entries = Vec::new();
+        let last = lines.len().saturating_sub(1);
+        for (i, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = match json::parse(line) {
+                Ok(entry) => entry,
+                // Only the entry being written when the run was interrupted
+                // may be incomplete.
+                Err(_) if i == last => break,
+                Err(e) => {
+                    return Err(AcvpError::parse_json(
+                        format!("Invalid journal entry on line {}", i + 1),
+                        e,
+                    ));
+                }
+            };
+            let entry = JournalEntry::from_json(&entry).map_err(|e| {
+                AcvpError::parse(format!(
+                    "Invalid journal entry on line {}: {}",
+                    i + 1,
+                    e.message()
+                ))
+            })?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
+
+impl<T: TestCase> AcvpRequest<T> {
+    /// Restore the results recorded in the journal at `path` for this
+    /// vector set, entries of other vector sets are skipped. The restored
+    /// results are reported by `get_result` until a result is set on the
+    /// test. Returns the number of tests restored.
+    pub fn resume<P: AsRef<Path>>(&mut self, path: P) -> AcvpResult<usize> {
+        let mut restored = 0;
+        for entry in Journal::entries(path)? {
+            if entry.vsid != self.vsid {
+                continue;
+            }
+            let tg = match self.testgroups.iter_mut().find(|g| g.tgid == entry.tgid) {
+                Some(tg) => tg,
+                None => {
+                    return Err(AcvpError::invalid("Test group is not part of the request")
+                        .with_vsid(self.vsid)
+                        .with_tgid(entry.tgid));
+                }
+            };
+            let test = match tg.tests.iter_mut().find(|t| t.tcid == entry.tcid) {
+                Some(test) => test,
+                None => {
+                    return Err(AcvpError::invalid("Test case is not part of the request")
+                        .with_vsid(self.vsid)
+                        .with_tgid(entry.tgid)
+                        .with_tcid(entry.tcid));
+                }
+            };
+            if test.stored_result.is_none() {
+                restored += 1;
+            }
+            test.stored_result = Some(entry.result);
+            if entry.group_result.is_some() {
+                tg.stored_result = entry.group_result;
+            }
+        }
+        Ok(restored)
+    }
+
+    /// Tests which have no result yet, along with the tgId of their group.
+    pub fn remaining_tests(&mut self) -> impl Iterator<Item = (u32, &mut AcvpTest<T>)> {
+        self.testgroups.iter_mut().flat_map(|tg| {
+            let tgid = tg.tgid;
+            tg.tests
+                .iter_mut()
+                .filter(|t| matches!(t.get_result(), Err(AcvpError::ResultNotSet { .. })))
+                .map(move |t| (tgid, t))
+        })
+    }
+}
@@ -3,53 +3,2417 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::fmt;
+use std::{error::Error, fmt, sync::Arc};
 
 pub type AcvpResult<T> = std::result::Result<T, AcvpError>;
 
+/// Underlying error an `AcvpError` was caused by.
+pub type ErrorSource = Arc<dyn Error + Send + Sync>;
+
+/// Location of an error within a vector set or response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// JSON path of the offending value, e.g. `$[1].testGroups[0].tests[3].pt`.
+    pub path: String,
+    pub vsid: Option<u32>,
+    pub tgid: Option<u32>,
+    pub tcid: Option<u32>,
+}
+
+impl ErrorContext {
+    pub fn is_empty(&self) -> bool {
+        self.path.is_empty() && self.vsid.is_none() && self.tgid.is_none() && self.tcid.is_none()
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut ids = Vec::new();
+        if let Some(vsid) = self.vsid {
+            ids.push(format!("vsId {}", vsid));
+        }
+        if let Some(tgid) = self.tgid {
+            ids.push(format!("tgId {}", tgid));
+        }
+        if let Some(tcid) = self.tcid {
+            ids.push(format!("tcId {}", tcid));
+        }
+        match (self.path.is_empty(), ids.is_empty()) {
+            (false, false) => write!(f, "{} ({})", self.path, ids.join(", ")),
+            (false, true) => write!(f, "{}", self.path),
+            (true, _) => write!(f, "{}", ids.join(", ")),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct AcvpError {
-    pub code: i32,
-    pub message: String,
+pub enum AcvpError {
+    /// The input is not valid JSON or is not shaped like an ACVP document.
+    Parse {
+        message: String,
+        context: ErrorContext,
+        source: Option<ErrorSource>,
+    },
+    /// A required field is absent or does not have the expected JSON type.
+    MissingField {
+        field: String,
+        expected: &'static str,
+        context: ErrorContext,
+    },
+    /// A field does not hold a valid hex string.
+    BadHex {
+        value: String,
+        context: ErrorContext,
+        source: Option<ErrorSource>,
+    },
+    /// The algorithm, mode or a primitive named by the vector set is not
+    /// supported.
+    UnsupportedAlgorithm {
+        message: String,
+        context: ErrorContext,
+    },
+    /// The response was requested before the result of a test was set.
+    ResultNotSet { context: ErrorContext },
+    /// The result set does not fit the test.
+    InvalidResult {
+        message: String,
+        context: ErrorContext,
+    },
+    /// Reading the vector set or writing the response failed.
+    Io {
+        context: ErrorContext,
+        source: ErrorSource,
+    },
+    /// Any other invalid input, `code` is an errno style code.
+    Invalid {
+        code: i32,
+        message: String,
+        context: ErrorContext,
+    },
+}
+
+impl AcvpError {
+    pub fn parse(message: impl Into<String>) -> Self {
+        Self::Parse {
+            message: message.into(),
+            context: ErrorContext::default(),
+            source: None,
+        }
+    }
+
+    pub fn parse_json(message: impl Into<String>, source: json::Error) -> Self {
+        Self::Parse {
+            message: message.into(),
+            context: ErrorContext::default(),
+            source: Some(Arc::new(source)),
+        }
+    }
+
+    pub fn missing_field(field: &str, expected: &'static str) -> Self {
+        Self::MissingField {
+            field: field.to_string(),
+            expected,
+            context: ErrorContext {
+                path: field.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn bad_hex(value: &str, source: hex::FromHexError) -> Self {
+        Self::BadHex {
+            value: value.to_string(),
+            context: ErrorContext::default(),
+            source: Some(Arc::new(source)),
+        }
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        Self::UnsupportedAlgorithm {
+            message: message.into(),
+            context: ErrorContext::default(),
+        }
+    }
+
+    pub fn result_not_set() -> Self {
+        Self::ResultNotSet {
+            context: ErrorContext::default(),
+        }
+    }
+
+    pub fn invalid_result(message: impl Into<String>) -> Self {
+        Self::InvalidResult {
+            message: message.into(),
+            context: ErrorContext::default(),
+        }
+    }
+
+    pub fn io(source: std::io::Error) -> Self {
+        Self::Io {
+            context: ErrorContext::default(),
+            source: Arc::new(source),
+        }
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::with_code(-libc::EINVAL, message)
+    }
+
+    pub fn with_code(code: i32, message: impl Into<String>) -> Self {
+        Self::Invalid {
+            code,
+            message: message.into(),
+            context: ErrorContext::default(),
+        }
+    }
+
+    /// Errno style code of the error, as reported before the error kinds
+    /// were introduced.
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::Invalid { code, .. } => *code,
+            Self::Io { .. } => -libc::EIO,
+            _ => -libc::EINVAL,
+        }
+    }
+
+    /// Description of the error without its location.
+    pub fn message(&self) -> String {
+        match self {
+            Self::Parse { message, .. }
+            | Self::UnsupportedAlgorithm { message, .. }
+            | Self::InvalidResult { message, .. }
+            | Self::Invalid { message, .. } => message.clone(),
+            Self::MissingField {
+                field, expected, ..
+            } => format!(
+                "Failed to obtain {} value associated with key '{}'",
+                expected, field
+            ),
+            Self::BadHex { value, .. } => format!("Invalid hex string '{}'", value),
+            Self::ResultNotSet { .. } => {
+                "The result is not yet set, call set_result API".to_string()
+            }
+            Self::Io { source, .. } => format!("I/O error: {}", source),
+        }
+    }
+
+    pub fn context(&self) -> &ErrorContext {
+        match self {
+            Self::Parse { context, .. }
+            | Self::MissingField { context, .. }
+            | Self::BadHex { context, .. }
+            | Self::UnsupportedAlgorithm { context, .. }
+            | Self::ResultNotSet { context }
+            | Self::Io { context, .. }
+            | Self::InvalidResult { context, .. }
+            | Self::Invalid { context, .. } => context,
+        }
+    }
+
+    pub fn context_mut(&mut self) -> &mut ErrorContext {
+        match self {
+            Self::Parse { context, .. }
+            | Self::MissingField { context, .. }
+            | Self::BadHex { context, .. }
+            | Self::UnsupportedAlgorithm { context, .. }
+            | Self::ResultNotSet { context }
+            | Self::Io { context, .. }
+            | Self::InvalidResult { context, .. }
+            | Self::Invalid { context, .. } => context,
+        }
+    }
+
+    /// Prefix the JSON path of the error with the path of its parent, e.g.
+    /// `tests[3]` or `[1]`.
+    pub fn at(mut self, parent: &str) -> Self {
+        let path = &mut self.context_mut().path;
+        if path.is_empty() {
+            *path = parent.to_string();
+        } else if path.starts_with('[') {
+            path.insert_str(0, parent);
+        } else {
+            *path = format!("{}.{}", parent, path);
+        }
+        self
+    }
+
+    /// Set the vsId of the error unless already known.
+    pub fn with_vsid(mut self, vsid: u32) -> Self {
+        self.context_mut().vsid.get_or_insert(vsid);
+        self
+    }
+
+    /// Set the tgId of the error unless already known.
+    pub fn with_tgid(mut self, tgid: u32) -> Self {
+        self.context_mut().tgid.get_or_insert(tgid);
+        self
+    }
+
+    /// Set the tcId of the error unless already known.
+    pub fn with_tcid(mut self, tcid: u32) -> Self {
+        self.context_mut().tcid.get_or_insert(tcid);
+        self
+    }
 }
 
 impl fmt::Display for AcvpError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} ({})", &self.message, &self.code)
+        write!(f, "{} ({})", self.message(), self.code())?;
+        if !self.context().is_empty() {
+            write!(f, " at {}", self.context())?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for AcvpError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Parse {
+                source: Some(source),
+                ..
+            }
+            | Self::BadHex {
+                source: Some(source),
+                ..
+            } => Some(source.as_ref()),
+            Self::Io { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
     }
 }
 
+pub mod ascon;
+pub mod backend;
 pub mod blkcipher;
+pub mod conditioning;
+pub mod dispatch;
 pub mod drbg;
+pub mod dsa;
+pub mod execute;
 pub mod hash;
+pub mod ifc;
+pub mod journal;
 pub mod msgauth;
 pub mod parser;
+#[cfg(test)]
+mod refimpl;
+pub mod registry;
+pub mod response;
+pub mod rsa;
+pub mod safeprimes;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod stream;
 pub mod util;
+pub mod validate;
+pub mod verify;
 
 #[cfg(test)]
 #[test]
 fn test_init() {
     use hash::SecureHash;
 
-    use crate::parser::{AcvpTest, TestCase, TestGroupData, TestResult};
-    let tgdata = TestGroupData {
-        algorithm: "sha1".to_string(),
+    use crate::parser::{AcvpTest, TestCase, TestResult};
+    let tgdata = hash::HashGroupData {
+        algorithm: util::AcvpAlgorithm::Hash(util::HashAlg::SHA1),
         test_type: util::TestType::AFT,
-        taglen: 0,
-        payload_len: 0,
-        ivmode: util::IVMode::Nil,
-        ivlen: 0,
-        direction: util::Direction::Nil,
-        drbgmode: drbg::DrbgMode::Nil,
-        prediction_resistance: false,
-        der_func: false,
-        reseed: false,
-        returned_bits_len: 0,
+        min_out_len: 0,
+        max_out_len: 0,
     };
 
-    let mut t =
-        AcvpTest::<SecureHash>::new(r#"{ "tcId": 10, "msg": "abcdef" }"#, &tgdata).expect("Failed");
+    let tc = json::object! { tcId: 10, msg: "abcdef" };
+    let mut t = AcvpTest::<SecureHash>::new(&tc, &tgdata).expect("Failed");
     t.set_result(vec![0xa, 0xb, 0xc, 0xd, 0xe, 0xf])
         .expect("Failed to set result");
     println!("{}", t.pretty_result().expect("Failed dump"));
 }
+
+#[cfg(test)]
+#[test]
+fn test_ascon_aead() {
+    use ascon::AsconAead;
+    use msgauth::MsgAuthOutput;
+
+    use crate::parser::{AcvpRequest, TestResult};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 1, "algorithm": "Ascon-AEAD128", "revision": "SP800-232", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "direction": "encrypt", "nonceMasking": true,
+              "tests": [
+                { "tcId": 1, "key": "000102030405060708090A0B0C0D0E0F",
+                  "nonce": "101112131415161718191A1B1C1D1E1F",
+                  "secondKey": "202122232425262728292A2B2C2D2E2F",
+                  "ad": "30", "adLen": 4, "pt": "", "payloadLen": 0, "tagLen": 128 }
+              ]
+            }
+          ]
+        }
+    ]"#;
+
+    let mut req = AcvpRequest::<AsconAead>::new(vector).expect("Failed to parse request");
+    let test = &mut req.testgroups[0].tests[0];
+    assert!(test.test.nonce_masking);
+    assert_eq!(test.test.ad_len, 4);
+    assert_eq!(test.test.second_key.as_ref().map(|k| k.len()), Some(16));
+    assert!(test.set_result(false).is_err());
+    test.set_result(MsgAuthOutput::new(Vec::new(), vec![0xab; 16]))
+        .expect("Failed to set result");
+    println!("{}", req.pretty_result().expect("Failed dump"));
+
+    // Only a tag failure of a decryption is reported as a bool.
+    let vector = vector.replace(r#""encrypt""#, r#""decrypt""#).replace(
+        r#""pt": """#,
+        r#""ct": "", "tag": "00000000000000000000000000000000""#,
+    );
+    let mut req = AcvpRequest::<AsconAead>::new(&vector).expect("Failed to parse request");
+    let test = &mut req.testgroups[0].tests[0];
+    assert!(matches!(
+        test.set_result(true),
+        Err(AcvpError::InvalidResult { .. })
+    ));
+    test.set_result(false).expect("Failed to set result");
+    let res = req.get_result().expect("Failed to get result");
+    assert_eq!(res[1]["testGroups"][0]["tests"][0]["testPassed"], false);
+}
+
+#[cfg(test)]
+#[test]
+fn test_dsa_keygen() {
+    use dsa::{Dsa, DsaKeyOutput};
+
+    use crate::parser::{AcvpRequest, TestResult};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 2, "algorithm": "DSA", "mode": "keyGen", "revision": "FIPS186-4", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "l": 2048, "n": 224,
+              "tests": [ { "tcId": 1 }, { "tcId": 2 } ] }
+          ]
+        }
+    ]"#;
+
+    let mut req = AcvpRequest::<Dsa>::new(vector).expect("Failed to parse request");
+    assert_eq!(req.mode, "keyGen");
+    for test in &mut req.testgroups[0].tests {
+        let out = DsaKeyOutput::new(vec![0xa], vec![0xb], vec![0xc], vec![0xd], vec![0xe]);
+        test.set_result(out).expect("Failed to set result");
+    }
+    let res = req.get_result().expect("Failed to get result");
+    assert_eq!(res[1]["mode"], "keyGen");
+    let tg = &res[1]["testGroups"][0];
+    assert_eq!(tg["p"], "0A");
+    assert_eq!(tg["tests"][1]["y"], "0E");
+
+    // Domain parameters are reported once per group, a test which generated
+    // different ones fails the result instead of being reported with the
+    // parameters of the first test.
+    let out = DsaKeyOutput::new(vec![0xf], vec![0xb], vec![0xc], vec![0xd], vec![0xe]);
+    req.testgroups[0].tests[1]
+        .set_result(out)
+        .expect("Failed to set result");
+    let err = req.get_result().expect_err("Mismatching group result");
+    assert!(matches!(err, AcvpError::InvalidResult { .. }));
+    let ctx = err.context();
+    assert_eq!((ctx.tgid, ctx.tcid), (Some(1), Some(2)));
+}
+
+#[cfg(test)]
+#[test]
+fn test_dsa_modes() {
+    use dsa::{Dsa, DsaKeyOutput, DsaPQGOutput, DsaSigOutput, GMode, PQMode};
+
+    use crate::parser::{AcvpRequest, TestCase, TestResult};
+    let vector = |mode: &str, groups: &str| {
+        format!(
+            r#"[ {{ "acvVersion": "1.0" }},
+                 {{ "vsId": 5, "algorithm": "DSA", "mode": "{}", "revision": "FIPS186-4",
+                    "isSample": true, "testGroups": [ {} ] }} ]"#,
+            mode, groups
+        )
+    };
+
+    // pqgGen: probable and provable p and q, and g generation.
+    let groups = r#"
+        { "tgId": 1, "testType": "GDT", "l": 2048, "n": 224, "hashAlg": "SHA2-224",
+          "pqMode": "probable", "tests": [ { "tcId": 1 } ] },
+        { "tgId": 2, "testType": "GDT", "l": 2048, "n": 224, "hashAlg": "SHA2-224",
+          "pqMode": "provable", "tests": [ { "tcId": 2 } ] },
+        { "tgId": 3, "testType": "GDT", "l": 2048, "n": 224, "hashAlg": "SHA2-224",
+          "gMode": "canonical",
+          "tests": [ { "tcId": 3, "p": "0D", "q": "0B", "domainSeed": "0102", "index": "A5" } ] }"#;
+    let mut req = AcvpRequest::<Dsa>::new(&vector("pqgGen", groups)).expect("Failed to parse");
+    assert_eq!(req.testgroups[0].tgdata.pqmode, PQMode::Probable);
+    assert_eq!(req.testgroups[1].tgdata.pqmode, PQMode::Provable);
+    assert_eq!(req.testgroups[2].tgdata.gmode, GMode::Canonical);
+    assert_eq!(
+        req.testgroups[0].tgdata.hash_alg,
+        Some(util::HashAlg::SHA224)
+    );
+    let gen = &req.testgroups[2].tests[0].test;
+    assert_eq!((gen.p.clone(), gen.q.clone()), (vec![0xd], vec![0xb]));
+    assert_eq!(
+        (gen.domain_seed.clone(), gen.index.clone()),
+        (vec![1, 2], vec![0xa5])
+    );
+
+    let test = &mut req.testgroups[0].tests[0];
+    let key = DsaKeyOutput::new(vec![1], vec![2], vec![3], vec![4], vec![5]);
+    assert!(matches!(
+        test.set_result(key),
+        Err(AcvpError::InvalidResult { .. })
+    ));
+    test.set_result(DsaPQGOutput::new_probable(
+        vec![0xd],
+        vec![0xb],
+        vec![0xaa],
+        7,
+    ))
+    .expect("Failed to set result");
+    req.testgroups[1].tests[0]
+        .set_result(DsaPQGOutput::new_provable(
+            vec![0xd],
+            vec![0xb],
+            vec![0xaa],
+            8,
+            9,
+        ))
+        .expect("Failed to set result");
+    req.testgroups[2].tests[0]
+        .set_result(DsaPQGOutput::new_g(vec![0x00, 0x05]))
+        .expect("Failed to set result");
+    let res = req.get_result().expect("Failed to get result");
+    let tgs = &res[1]["testGroups"];
+    let probable = &tgs[0]["tests"][0];
+    assert_eq!(
+        (probable["p"].as_str(), probable["q"].as_str()),
+        (Some("0D"), Some("0B"))
+    );
+    assert_eq!(probable["domainSeed"], "AA");
+    assert_eq!(probable["counter"], 7);
+    let provable = &tgs[1]["tests"][0];
+    assert_eq!(
+        (provable["pCounter"].as_u32(), provable["qCounter"].as_u32()),
+        (Some(8), Some(9))
+    );
+    assert!(!provable.has_key("counter"));
+    assert_eq!(tgs[2]["tests"][0]["g"], "05");
+    assert!(!tgs[2]["tests"][0].has_key("p"));
+
+    // pqgVer: the candidate parameters and their seeds are parsed per test.
+    let groups = r#"
+        { "tgId": 1, "testType": "GDT", "l": 2048, "n": 224, "hashAlg": "SHA2-224",
+          "pqMode": "probable",
+          "tests": [ { "tcId": 1, "p": "0D", "q": "0B", "domainSeed": "0102", "counter": 5 } ] },
+        { "tgId": 2, "testType": "GDT", "l": 2048, "n": 224, "hashAlg": "SHA2-224",
+          "gMode": "unverifiable",
+          "tests": [ { "tcId": 2, "p": "0D", "q": "0B", "g": "03", "domainSeed": "0102",
+                       "h": "02" } ] }"#;
+    let mut req = AcvpRequest::<Dsa>::new(&vector("pqgVer", groups)).expect("Failed to parse");
+    let test = &req.testgroups[0].tests[0].test;
+    assert_eq!((test.domain_seed.clone(), test.counter), (vec![1, 2], 5));
+    let test = &req.testgroups[1].tests[0].test;
+    assert_eq!(req.testgroups[1].tgdata.gmode, GMode::Unverifiable);
+    assert_eq!((test.g.clone(), test.h.clone()), (vec![3], vec![2]));
+    let test = &mut req.testgroups[0].tests[0];
+    assert!(test.set_result(DsaPQGOutput::new_g(vec![0x05])).is_err());
+    test.set_result(true).expect("Failed to set result");
+    req.testgroups[1].tests[0]
+        .set_result(false)
+        .expect("Failed to set result");
+    let res = req.get_result().expect("Failed to get result");
+    assert_eq!(res[1]["testGroups"][1]["tests"][0]["testPassed"], false);
+    assert!(AcvpRequest::<Dsa>::new(&vector(
+        "pqgVer",
+        &groups.replace(r#""gMode": "unverifiable","#, "")
+    ))
+    .is_err());
+
+    // sigGen: the key is reported once per group.
+    let groups = r#"
+        { "tgId": 1, "testType": "AFT", "l": 2048, "n": 224, "hashAlg": "SHA2-256",
+          "tests": [ { "tcId": 1, "message": "0102" } ] }"#;
+    let mut req = AcvpRequest::<Dsa>::new(&vector("sigGen", groups)).expect("Failed to parse");
+    let test = &mut req.testgroups[0].tests[0];
+    assert_eq!(test.test.msg, vec![1, 2]);
+    assert!(test.set_result(true).is_err());
+    let sig = DsaSigOutput::new(vec![0xd], vec![0xb], vec![3], vec![4], vec![5], vec![6]);
+    test.set_result(sig).expect("Failed to set result");
+    let res = req.get_result().expect("Failed to get result");
+    let tg = &res[1]["testGroups"][0];
+    assert_eq!(
+        (tg["p"].as_str(), tg["y"].as_str()),
+        (Some("0D"), Some("04"))
+    );
+    assert_eq!(
+        (tg["tests"][0]["r"].as_str(), tg["tests"][0]["s"].as_str()),
+        (Some("05"), Some("06"))
+    );
+
+    // sigVer: the domain parameters are given per group.
+    let groups = r#"
+        { "tgId": 1, "testType": "GDT", "l": 2048, "n": 224, "hashAlg": "SHA2-256",
+          "p": "0D", "q": "0B", "g": "03",
+          "tests": [ { "tcId": 1, "message": "0102", "y": "04", "r": "05", "s": "06" } ] }"#;
+    let mut req = AcvpRequest::<Dsa>::new(&vector("sigVer", groups)).expect("Failed to parse");
+    let test = &mut req.testgroups[0].tests[0];
+    assert_eq!(
+        (test.test.p.clone(), test.test.g.clone()),
+        (vec![0xd], vec![3])
+    );
+    assert_eq!(
+        (test.test.y.clone(), test.test.s.clone()),
+        (vec![4], vec![6])
+    );
+    let sig = DsaSigOutput::new(vec![0xd], vec![0xb], vec![3], vec![4], vec![5], vec![6]);
+    assert!(test.set_result(sig).is_err());
+    test.set_result(true).expect("Failed to set result");
+    assert_eq!(test.get_result().expect("No result")["testPassed"], true);
+    assert!(
+        AcvpRequest::<Dsa>::new(&vector("sigVer", &groups.replace(r#""g": "03","#, ""))).is_err()
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_kts_ifc() {
+    use ifc::{Ifc, IfcKeyFormat, IfcOutput, KasRole};
+
+    use crate::parser::{AcvpRequest, TestResult};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 3, "algorithm": "KTS-IFC", "revision": "Sp800-56Br2", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "scheme": "KTS-OAEP-basic", "kasRole": "initiator",
+              "keyGenerationMethod": "rsakpg1-crt", "modulo": 2048, "l": 1024,
+              "fixedPubExp": "010001", "ktsConfiguration": { "hashAlg": "SHA2-256" },
+              "tests": [
+                { "tcId": 1, "serverN": "0C0FFEE", "serverE": "10001",
+                  "iutP": "00F3", "iutQ": "E1", "iutDmp1": "11", "iutDmq1": "12", "iutIqmp": "13" }
+              ]
+            }
+          ]
+        }
+    ]"#;
+
+    let mut req = AcvpRequest::<Ifc>::new(vector).expect("Failed to parse request");
+    let test = &mut req.testgroups[0].tests[0];
+    assert_eq!(test.test.kas_role, KasRole::Initiator);
+    assert_eq!(test.test.key_format, IfcKeyFormat::Crt);
+    assert_eq!(test.test.hash_alg, Some(util::HashAlg::SHA256));
+    assert_eq!(test.test.server_key.n, vec![0xc0, 0xff, 0xee]);
+    assert_eq!(test.test.iut_key.p, vec![0xf3]);
+    assert_eq!(test.test.iut_key.e, vec![0x01, 0x00, 0x01]);
+    test.set_result(IfcOutput::new(vec![0x1], vec![0x2]))
+        .expect("Failed to set result");
+    let res = req.get_result().expect("Failed to get result");
+    assert_eq!(res[1]["testGroups"][0]["tests"][0]["dkm"], "02");
+}
+
+#[cfg(test)]
+#[test]
+fn test_safe_primes() {
+    use dispatch::AnyResult;
+    use safeprimes::{SafePrimes, SafePrimesKeyOutput, SafePrimesMode};
+
+    use crate::parser::{AcvpRequest, TestCase};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 14, "algorithm": "safePrimes", "mode": "keyGen", "revision": "1.0",
+          "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "safePrimeGroup": "ffdhe2048",
+              "tests": [ { "tcId": 1 } ] }
+          ]
+        }
+    ]"#;
+
+    let mut req = AcvpRequest::<SafePrimes>::new(vector).expect("Failed to parse request");
+    let test = &mut req.testgroups[0].tests[0];
+    assert_eq!(test.test.mode, SafePrimesMode::KeyGen);
+    assert_eq!(test.test.safe_prime_group, "ffdhe2048");
+    assert!(matches!(
+        test.set_any_result(AnyResult::Bool(true)),
+        Err(AcvpError::InvalidResult { .. })
+    ));
+    let key = SafePrimesKeyOutput::new(vec![0x00, 0x1a], vec![0x2b]);
+    test.set_any_result(AnyResult::SafePrimesKey(key))
+        .expect("Failed to set result");
+    let res = req.get_result().expect("Failed to get result");
+    let tc = &res[1]["testGroups"][0]["tests"][0];
+    assert_eq!(
+        (tc["x"].as_str(), tc["y"].as_str()),
+        (Some("1A"), Some("2B"))
+    );
+    assert_eq!(res[1]["mode"], "keyGen");
+
+    let vector = vector.replace("keyGen", "keyVer").replace(
+        r#"{ "tcId": 1 }"#,
+        r#"{ "tcId": 1, "x": "1A", "y": "002B" }"#,
+    );
+    let mut req = AcvpRequest::<SafePrimes>::new(&vector).expect("Failed to parse request");
+    let test = &mut req.testgroups[0].tests[0];
+    assert_eq!(
+        (test.test.x.clone(), test.test.y.clone()),
+        (vec![0x1a], vec![0x2b])
+    );
+    let key = SafePrimesKeyOutput::new(vec![0x1a], vec![0x2b]);
+    assert!(matches!(
+        test.set_any_result(AnyResult::SafePrimesKey(key)),
+        Err(AcvpError::InvalidResult { .. })
+    ));
+    test.set_any_result(AnyResult::Bool(false))
+        .expect("Failed to set result");
+    let res = req.get_result().expect("Failed to get result");
+    assert_eq!(res[1]["testGroups"][0]["tests"][0]["testPassed"], false);
+    assert!(AcvpRequest::<SafePrimes>::new(&vector.replace(r#", "x": "1A""#, "")).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_rsa_primitive() {
+    use rsa::{RsaPrimitive, RsaPrimitiveMode};
+
+    use crate::parser::{AcvpRequest, TestCase, TestResult};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 11, "algorithm": "RSA", "mode": "decryptionPrimitive", "revision": "Sp800-56Br2",
+          "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "modulo": 2048, "keyFormat": "crt",
+              "tests": [
+                { "tcId": 1, "n": "C0FFEE", "e": "010001", "p": "F3", "q": "E1", "dmp1": "11",
+                  "dmq1": "12", "iqmp": "13", "c": "0ABC" },
+                { "tcId": 2, "n": "C0FFEE", "e": "010001", "p": "F3", "q": "E1", "dmp1": "11",
+                  "dmq1": "12", "iqmp": "13", "c": "FFFFFF" }
+              ]
+            }
+          ]
+        }
+    ]"#;
+
+    let mut req = AcvpRequest::<RsaPrimitive>::new(vector).expect("Failed to parse request");
+    let test = &mut req.testgroups[0].tests[0];
+    assert_eq!(test.test.mode, RsaPrimitiveMode::DecryptionPrimitive);
+    assert_eq!(test.test.modulo, 2048);
+    assert_eq!(test.test.key.n, vec![0xc0, 0xff, 0xee]);
+    assert_eq!(test.test.key.dmp1, vec![0x11]);
+    assert_eq!(test.test.input, vec![0x0a, 0xbc]);
+    test.set_result(vec![0x1, 0x2])
+        .expect("Failed to set result");
+
+    // Out of range input: the failure is reported without output, and a
+    // success without output is rejected.
+    let test = &mut req.testgroups[0].tests[1];
+    assert!(matches!(
+        test.set_result(true),
+        Err(AcvpError::InvalidResult { .. })
+    ));
+    test.set_result(false).expect("Failed to set result");
+
+    let res = req.get_result().expect("Failed to get result");
+    let tests = &res[1]["testGroups"][0]["tests"];
+    assert_eq!(tests[0]["testPassed"], true);
+    assert_eq!(tests[0]["pt"], "0102");
+    assert_eq!(tests[1]["testPassed"], false);
+    assert!(!tests[1].has_key("pt"));
+
+    let vector = vector
+        .replace("decryptionPrimitive", "signaturePrimitive")
+        .replace(r#""c": "0ABC""#, r#""message": "0ABC""#)
+        .replace(r#""c": "FFFFFF""#, r#""message": "FFFFFF""#);
+    let mut req = AcvpRequest::<RsaPrimitive>::new(&vector).expect("Failed to parse request");
+    let test = &mut req.testgroups[0].tests[0];
+    assert_eq!(test.test.mode, RsaPrimitiveMode::SignaturePrimitive);
+    assert_eq!(test.test.input, vec![0x0a, 0xbc]);
+    test.set_result(vec![0x3]).expect("Failed to set result");
+    let res = test.get_result().expect("Failed to get result");
+    assert_eq!(res["signature"], "03");
+}
+
+#[cfg(test)]
+#[test]
+fn test_conditioning() {
+    use conditioning::{Conditioning, ConditioningMode};
+
+    use crate::parser::{AcvpRequest, TestResult};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 12, "algorithm": "ConditioningComponent", "mode": "Hash_DF",
+          "revision": "SP800-90B", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "hashAlg": "SHA2-256", "payloadLen": 16,
+              "outputLen": 256,
+              "tests": [ { "tcId": 1, "payload": "ABCD" },
+                         { "tcId": 2, "payload": "ABCDEF", "payloadLen": 20 } ] }
+          ]
+        }
+    ]"#;
+
+    let mut req = AcvpRequest::<Conditioning>::new(vector).expect("Failed to parse request");
+    let tgdata = &req.testgroups[0].tgdata;
+    assert_eq!(tgdata.mode, ConditioningMode::HashDf);
+    assert_eq!(tgdata.hash_alg, Some(util::HashAlg::SHA256));
+    assert_eq!(tgdata.output_len, 256);
+    let tests = &mut req.testgroups[0].tests;
+    assert_eq!(tests[0].test.payload, vec![0xab, 0xcd]);
+    assert_eq!(tests[0].test.payload_len, 16);
+    assert_eq!(tests[1].test.payload_len, 20);
+    assert!(tests[0].test.key.is_empty());
+    for test in tests.iter_mut() {
+        test.set_result(vec![0x5a; 32])
+            .expect("Failed to set result");
+    }
+    let res = req.get_result().expect("Failed to get result");
+    let tc = &res[1]["testGroups"][0]["tests"][1];
+    assert_eq!(tc["tcId"], 2);
+    assert_eq!(tc["output"], "5A".repeat(32));
+
+    // The block cipher based modes need a key instead of a hash.
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 13, "algorithm": "ConditioningComponent", "mode": "CBC-MAC",
+          "revision": "SP800-90B", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "keyLen": 128, "payloadLen": 256, "outputLen": 128,
+              "tests": [ { "tcId": 1, "key": "000102030405060708090A0B0C0D0E0F",
+                           "payload": "00000000000000000000000000000000" } ] }
+          ]
+        }
+    ]"#;
+    let req = AcvpRequest::<Conditioning>::new(vector).expect("Failed to parse request");
+    let test = &req.testgroups[0].tests[0].test;
+    assert_eq!(test.mode, ConditioningMode::CbcMac);
+    assert_eq!(test.key_len, 128);
+    assert_eq!(test.key, (0..16).collect::<Vec<u8>>());
+    assert!(AcvpRequest::<Conditioning>::new(&vector.replace(r#""keyLen": 128, "#, "")).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_drbg_optional_inputs() {
+    use drbg::{Drbg, DrbgIntendedUse};
+
+    use crate::parser::AcvpRequest;
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 4, "algorithm": "ctrDRBG", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "mode": "AES-128", "derFunc": false,
+              "predResistance": false, "reSeed": true, "returnedBitsLen": 512,
+              "tests": [
+                { "tcId": 1, "entropyInput": "000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F",
+                  "nonce": "", "persoString": "",
+                  "otherInput": [
+                    { "intendedUse": "reSeed", "additionalInput": "",
+                      "entropyInput": "202122232425262728292A2B2C2D2E2F303132333435363738393A3B3C3D3E3F" },
+                    { "intendedUse": "generate", "additionalInput": "", "entropyInput": "" },
+                    { "intendedUse": "generate", "additionalInput": "", "entropyInput": "" }
+                  ]
+                }
+              ]
+            }
+          ]
+        }
+    ]"#;
+
+    let req = AcvpRequest::<Drbg>::new(vector).expect("Failed to parse request");
+    let test = &req.testgroups[0].tests[0].test;
+    assert!(test.reseed && !test.der_func && !test.prediction_resistance);
+    assert_eq!(test.returned_bits_len, 64);
+    assert_eq!(test.nonce, None);
+    assert_eq!(test.perso_string, None);
+    assert_eq!(test.other_input[0].iuse, DrbgIntendedUse::ReSeed);
+    assert_eq!(test.other_input[1].entropy_input, None);
+}
+
+#[cfg(test)]
+#[test]
+fn test_drbg_mode_tables() {
+    use drbg::DrbgMode;
+
+    // (mode, security strength, outlen, seedlen) per SP800-90A tables 2 and 3.
+    let table = [
+        (DrbgMode::TDES, 112, 64, 232),
+        (DrbgMode::AES128, 128, 128, 256),
+        (DrbgMode::AES192, 192, 128, 320),
+        (DrbgMode::AES256, 256, 128, 384),
+        (DrbgMode::SHA1, 128, 160, 440),
+        (DrbgMode::SHA224, 192, 224, 440),
+        (DrbgMode::SHA512_224, 192, 224, 440),
+        (DrbgMode::SHA256, 256, 256, 440),
+        (DrbgMode::SHA512_256, 256, 256, 440),
+        (DrbgMode::SHA384, 256, 384, 888),
+        (DrbgMode::SHA512, 256, 512, 888),
+        (DrbgMode::SHA3_224, 192, 224, 440),
+        (DrbgMode::SHA3_256, 256, 256, 440),
+        (DrbgMode::SHA3_384, 256, 384, 888),
+        (DrbgMode::SHA3_512, 256, 512, 888),
+    ];
+    for (mode, strength, out_len, seed_len) in table {
+        assert_eq!(
+            (mode.security_strength(), mode.out_len(), mode.seed_len()),
+            (strength, out_len, seed_len),
+            "{:?}",
+            mode
+        );
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_drbg_execute() {
+    use drbg::{Drbg, DrbgInstance};
+
+    use crate::parser::{AcvpRequest, TestCase};
+
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<String>,
+    }
+
+    impl DrbgInstance for Recorder {
+        fn instantiate(
+            &mut self,
+            test: &Drbg,
+            entropy_input: &[u8],
+            nonce: Option<&[u8]>,
+            _perso_string: Option<&[u8]>,
+        ) -> AcvpResult<()> {
+            self.calls.push(format!(
+                "inst {:?} {} {}",
+                test.drbg_type()?,
+                entropy_input.len(),
+                nonce.is_some()
+            ));
+            Ok(())
+        }
+
+        fn reseed(&mut self, entropy_input: &[u8], _addtl_input: &[u8]) -> AcvpResult<()> {
+            self.calls.push(format!("reseed {}", entropy_input.len()));
+            Ok(())
+        }
+
+        fn generate(
+            &mut self,
+            out_len: usize,
+            _addtl_input: &[u8],
+            entropy_input: Option<&[u8]>,
+        ) -> AcvpResult<Vec<u8>> {
+            self.calls.push(format!("gen {}", entropy_input.is_some()));
+            Ok(vec![self.calls.len() as u8; out_len])
+        }
+    }
+
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 5, "algorithm": "hashDRBG", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "mode": "SHA2-256", "derFunc": false,
+              "predResistance": true, "reSeed": false, "returnedBitsLen": 64,
+              "tests": [
+                { "tcId": 1, "entropyInput": "0001", "nonce": "02", "persoString": "",
+                  "otherInput": [
+                    { "intendedUse": "generate", "additionalInput": "", "entropyInput": "03" },
+                    { "intendedUse": "generate", "additionalInput": "", "entropyInput": "04" }
+                  ]
+                }
+              ]
+            }
+          ]
+        }
+    ]"#;
+
+    let mut req = AcvpRequest::<Drbg>::new(vector).expect("Failed to parse request");
+    let mut rec = Recorder::default();
+    let test = &mut req.testgroups[0].tests[0].test;
+    let out = test.execute(&mut rec).expect("Failed to execute DRBG");
+    assert_eq!(rec.calls, ["inst Hash 2 true", "gen true", "gen true"]);
+    assert_eq!(out, vec![3; 8]);
+    assert_eq!(
+        test.get_result().expect("Result not set")["returnedBits"],
+        "0303030303030303"
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_aead_output() {
+    use msgauth::{AeadOutput, MsgAuth};
+
+    use crate::parser::{AcvpRequest, TestResult};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 6, "algorithm": "ACVP-AES-GCM", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "direction": "encrypt", "ivGen": "internal",
+              "ivGenMode": "8.2.2", "ivLen": 96, "payloadLen": 0, "aadLen": 0, "tagLen": 128,
+              "tests": [ { "tcId": 1, "key": "00000000000000000000000000000000", "pt": "", "aad": "" } ]
+            },
+            { "tgId": 2, "testType": "AFT", "direction": "decrypt", "ivGen": "external",
+              "ivLen": 96, "payloadLen": 0, "aadLen": 0, "tagLen": 128,
+              "tests": [
+                { "tcId": 2, "key": "00000000000000000000000000000000", "ct": "", "aad": "",
+                  "iv": "000000000000000000000000", "tag": "58E2FCCEFA7E3061367F1D57A4E7455A" },
+                { "tcId": 3, "key": "00000000000000000000000000000000", "ct": "", "aad": "",
+                  "iv": "000000000000000000000000", "tag": "00000000000000000000000000000000" }
+              ]
+            }
+          ]
+        }
+    ]"#;
+
+    let mut req = AcvpRequest::<MsgAuth>::new(vector).expect("Failed to parse request");
+    let enc = &mut req.testgroups[0].tests[0];
+    assert!(enc
+        .set_result(AeadOutput::encrypt(Vec::new(), vec![0x1; 16]))
+        .is_err());
+    let mut counter = 0u8;
+    let mut gen = |buf: &mut [u8]| -> AcvpResult<()> {
+        for b in buf.iter_mut() {
+            *b = counter;
+            counter = counter.wrapping_add(1);
+        }
+        Ok(())
+    };
+    let iv = enc
+        .test
+        .generate_iv(&mut gen)
+        .expect("Failed to generate IV");
+    assert_eq!(iv, (0..12).collect::<Vec<u8>>());
+    enc.set_result(AeadOutput::encrypt_with_iv(
+        Vec::new(),
+        vec![0x1; 16],
+        vec![0xff; 12],
+    ))
+    .expect("Failed to set result");
+    req.testgroups[1].tests[0]
+        .set_result(AeadOutput::decrypt(Vec::new()))
+        .expect("Failed to set result");
+    req.testgroups[1].tests[1]
+        .set_result(AeadOutput::DecryptFail)
+        .expect("Failed to set result");
+    // IV and salt provided by the vector fail with the same error kind.
+    let dec = &mut req.testgroups[1].tests[0].test;
+    assert!(matches!(
+        dec.generate_iv(&mut gen),
+        Err(AcvpError::Invalid { .. })
+    ));
+    assert!(matches!(
+        dec.generate_salt(&mut gen),
+        Err(AcvpError::Invalid { .. })
+    ));
+
+    let res = req.get_result().expect("Failed to get result");
+    let tgs = &res[1]["testGroups"];
+    assert_eq!(tgs[0]["tests"][0]["iv"], "FFFFFFFFFFFFFFFFFFFFFFFF");
+    assert_eq!(
+        tgs[0]["tests"][0]["tag"],
+        "01010101010101010101010101010101"
+    );
+    assert_eq!(tgs[1]["tests"][0]["pt"], "");
+    assert_eq!(tgs[1]["tests"][1]["testPassed"], false);
+
+    // CCM decryption vectors carry the tag in the ciphertext, one shorter
+    // than the payload is rejected.
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 8, "algorithm": "ACVP-AES-CCM", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "direction": "decrypt", "ivLen": 56,
+              "payloadLen": 32, "aadLen": 0, "tagLen": 32,
+              "tests": [ { "tcId": 1, "key": "00000000000000000000000000000000",
+                           "iv": "00000000000000", "aad": "", "ct": "0011223344556677" } ]
+            }
+          ]
+        }
+    ]"#;
+    let req = AcvpRequest::<MsgAuth>::new(vector).expect("Failed to parse request");
+    let test = &req.testgroups[0].tests[0].test;
+    assert_eq!(test.msg, vec![0x00, 0x11, 0x22, 0x33]);
+    assert_eq!(test.tag, vec![0x44, 0x55, 0x66, 0x77]);
+    let err = AcvpRequest::<MsgAuth>::new(&vector.replace("0011223344556677", "001122"))
+        .expect_err("Short ciphertext accepted");
+    assert!(matches!(err, AcvpError::Invalid { .. }));
+}
+
+#[cfg(test)]
+#[test]
+fn test_aead_tag_truncation() {
+    use msgauth::{AeadOutput, MsgAuth};
+
+    use crate::parser::{AcvpRequest, TestCase, TestResult};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 7, "algorithm": "ACVP-AES-GCM", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "direction": "encrypt", "ivGen": "external",
+              "ivLen": 96, "payloadLen": 8, "aadLen": 0, "tagLen": 96,
+              "tests": [ { "tcId": 1, "key": "00000000000000000000000000000000", "pt": "AA",
+                           "aad": "", "iv": "000000000000000000000000" } ]
+            }
+          ]
+        }
+    ]"#;
+
+    // A full length tag is truncated to the tag length of the group.
+    let full_tag: Vec<u8> = (0..16).collect();
+    let mut req = AcvpRequest::<MsgAuth>::new(vector).expect("Failed to parse request");
+    let test = &mut req.testgroups[0].tests[0];
+    assert!(matches!(
+        test.set_result(AeadOutput::encrypt(vec![0xbb], vec![0; 8])),
+        Err(AcvpError::Invalid { .. })
+    ));
+    test.set_result(AeadOutput::encrypt(vec![0xbb], full_tag.clone()))
+        .expect("Failed to set result");
+    let res = test.get_result().expect("Failed to get result");
+    assert_eq!(res["tag"], "000102030405060708090A0B");
+    assert_eq!(res["ct"], "BB");
+
+    // CCM reports the truncated tag appended to the ciphertext.
+    let vector = vector.replace("ACVP-AES-GCM", "ACVP-AES-CCM");
+    let mut req = AcvpRequest::<MsgAuth>::new(&vector).expect("Failed to parse request");
+    let test = &mut req.testgroups[0].tests[0];
+    test.set_result(AeadOutput::encrypt(vec![0xbb], full_tag))
+        .expect("Failed to set result");
+    let res = test.get_result().expect("Failed to get result");
+    assert_eq!(res["ct"], "BB000102030405060708090A0B");
+    assert!(!res.has_key("tag"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_alg_type() {
+    use util::{AcvpAlgorithm, AeadMode, BlockCipherAlg, CipherMode, HashAlg, MacAlg};
+
+    let alg = AcvpAlgorithm::alg_type("ACVP-AES-GCM", "").expect("Failed");
+    assert_eq!(
+        alg,
+        AcvpAlgorithm::MsgAuth(MacAlg::AEAD(BlockCipherAlg::AES, AeadMode::GCM))
+    );
+    let alg = AcvpAlgorithm::alg_type("ACVP-TDES-CBC", "").expect("Failed");
+    assert_eq!(
+        alg,
+        AcvpAlgorithm::BlockCipher(BlockCipherAlg::TDES, CipherMode::CBC)
+    );
+    let alg = AcvpAlgorithm::alg_type("HMAC-SHA2-512/256", "").expect("Failed");
+    assert_eq!(
+        alg,
+        AcvpAlgorithm::MsgAuth(MacAlg::HMAC(HashAlg::SHA512_256))
+    );
+    let alg = AcvpAlgorithm::alg_type("DSA", "sigVer").expect("Failed");
+    assert_eq!(alg, AcvpAlgorithm::Dsa(dsa::DsaMode::SigVer));
+    assert!(AcvpAlgorithm::alg_type("ACVP-TDES-GCM", "").is_err());
+    assert!(AcvpAlgorithm::alg_type("DSA", "").is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_group_data_required() {
+    use drbg::{Drbg, DrbgGroupData, DrbgMode};
+
+    use crate::parser::{AcvpTestGroup, TestGroup};
+    let alg = util::AcvpAlgorithm::Rng(util::DrbgType::Hmac);
+    let tg = r#"{ "tgId": 1, "testType": "AFT", "mode": "SHA2-224", "predResistance": false,
+                  "reSeed": false, "returnedBitsLen": 896, "tests": [] }"#;
+    let group = AcvpTestGroup::<Drbg>::new(alg, &json::parse(tg).expect("Bad JSON"))
+        .expect("Failed to parse group");
+    assert_eq!(
+        group.tgdata,
+        DrbgGroupData {
+            algorithm: alg,
+            test_type: util::TestType::AFT,
+            mode: DrbgMode::SHA224,
+            der_func: false,
+            prediction_resistance: false,
+            reseed: false,
+            returned_bits_len: 112,
+        }
+    );
+
+    let tg = r#"{ "tgId": 1, "testType": "AFT", "mode": "SHA2-224", "predResistance": false,
+                  "reSeed": false, "tests": [] }"#;
+    assert!(AcvpTestGroup::<Drbg>::new(alg, &json::parse(tg).expect("Bad JSON")).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_any_request() {
+    use dispatch::{AnyRequest, AnyResult};
+    use hash::SecureHash;
+    use util::{AcvpAlgorithm, HashAlg};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 2, "algorithm": "SHA2-256", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT",
+              "tests": [ { "tcId": 1, "msg": "00", "len": 8 }, { "tcId": 2, "msg": "", "len": 0 } ]
+            }
+          ]
+        }
+    ]"#;
+
+    let mut req = AnyRequest::new(vector).expect("Failed to parse request");
+    assert_eq!(req.alg_type(), AcvpAlgorithm::Hash(HashAlg::SHA256));
+    for test in req.tests_mut() {
+        let len = test
+            .test_case()
+            .downcast_ref::<SecureHash>()
+            .map(|h| h.msg.len())
+            .expect("Not a hash test");
+        assert!(test.set_any_result(AnyResult::Bool(true)).is_err());
+        test.set_any_result(vec![len as u8; 32].into())
+            .expect("Failed to set result");
+    }
+    assert!(matches!(req, AnyRequest::Hash(_)));
+    println!("{}", req.pretty_result().expect("Failed dump"));
+
+    // The test groups and cases are moved out of the parsed tree, dumping
+    // puts them back in place.
+    let req = parser::AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    assert_eq!(json::parse(&req.dump()), json::parse(vector));
+}
+
+#[cfg(test)]
+#[test]
+fn test_custom_algorithm() {
+    use json::JsonValue;
+
+    use dispatch::AnyRequest;
+    use parser::{AcvpRequest, GroupData, TestCase, TestResult};
+    use registry::CustomAlgorithm;
+    use util::{AcvpAlgorithm, TestType};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct VendorGroupData {
+        algorithm: AcvpAlgorithm,
+    }
+
+    impl GroupData for VendorGroupData {
+        fn new(
+            algorithm: AcvpAlgorithm,
+            _test_type: TestType,
+            _tg: &JsonValue,
+        ) -> AcvpResult<Self> {
+            Ok(VendorGroupData { algorithm })
+        }
+    }
+
+    struct VendorKdf {
+        key: Vec<u8>,
+        res_json: JsonValue,
+    }
+
+    impl TestCase for VendorKdf {
+        type GroupData = VendorGroupData;
+
+        fn new(test: &JsonValue, _tgdata: &VendorGroupData) -> AcvpResult<Self> {
+            let key = util::get_acvp_str("key", test)?;
+            Ok(VendorKdf {
+                key: key.into_bytes(),
+                res_json: JsonValue::new_object(),
+            })
+        }
+
+        fn get_result(&self) -> AcvpResult<JsonValue> {
+            Ok(self.res_json.clone())
+        }
+
+        fn dump_result(&self) -> AcvpResult<String> {
+            Ok(self.res_json.dump())
+        }
+
+        fn pretty_result(&self) -> AcvpResult<String> {
+            Ok(self.res_json.pretty(3))
+        }
+    }
+
+    impl TestResult<Vec<u8>> for VendorKdf {
+        fn set_result(&mut self, res: Vec<u8>) -> AcvpResult<()> {
+            self.res_json["dkm"] = hex::encode(res).to_ascii_uppercase().into();
+            Ok(())
+        }
+    }
+
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 3, "algorithm": "VENDOR-KDF", "revision": "1.0", "isSample": true,
+          "testGroups": [ { "tgId": 1, "testType": "AFT", "tests": [ { "tcId": 1, "key": "ab" } ] } ]
+        }
+    ]"#;
+    assert!(util::get_algorithm_type(vector).is_err());
+
+    let alg = registry::register::<VendorKdf>(CustomAlgorithm {
+        algorithm: "VENDOR-KDF".to_string(),
+        mode: None,
+        revision: Some("1.0".to_string()),
+    });
+    assert_eq!(util::get_algorithm_type(vector).expect("Not routed"), alg);
+    assert!(AcvpAlgorithm::alg_type("VENDOR-KDF", "").is_err());
+
+    let mut req = AcvpRequest::<VendorKdf>::new(vector).expect("Failed to parse request");
+    assert_eq!(req.testgroups[0].tgdata.algorithm, alg);
+    req.testgroups[0].tests[0]
+        .set_result(vec![0xde, 0xad])
+        .expect("Failed to set result");
+
+    let any = AnyRequest::new(vector).expect("Failed to parse request");
+    assert_eq!(any.alg_type(), alg);
+    let test = any.tests()[0]
+        .test_case()
+        .downcast_ref::<VendorKdf>()
+        .expect("Not a vendor test");
+    assert_eq!(test.key, b"ab");
+}
+
+#[cfg(test)]
+#[test]
+fn test_error_context() {
+    use std::error::Error;
+
+    use hash::SecureHash;
+    use parser::AcvpRequest;
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 4, "algorithm": "SHA-1", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "tests": [ { "tcId": 1, "msg": "00", "len": 8 } ] },
+            { "tgId": 2, "testType": "AFT",
+              "tests": [ { "tcId": 2, "msg": "00", "len": 8 }, { "tcId": 3, "len": 8 } ] }
+          ]
+        }
+    ]"#;
+
+    let err = AcvpRequest::<SecureHash>::new(vector).expect_err("Missing msg accepted");
+    assert!(matches!(&err, AcvpError::MissingField { field, .. } if field == "msg"));
+    assert_eq!(err.code(), -libc::EINVAL);
+    let ctx = err.context();
+    assert_eq!(ctx.path, "$[1].testGroups[1].tests[1].msg");
+    assert_eq!((ctx.vsid, ctx.tgid, ctx.tcid), (Some(4), Some(2), Some(3)));
+    println!("{}", err);
+
+    let err = AcvpRequest::<SecureHash>::new("[ { ").expect_err("Bad JSON accepted");
+    assert!(matches!(err, AcvpError::Parse { .. }));
+    assert!(err.source().is_some());
+
+    let req = AcvpRequest::<SecureHash>::new(&vector.replace(r#", { "tcId": 3, "len": 8 }"#, ""))
+        .expect("Failed to parse request");
+    let err = req.get_result().expect_err("Result not set");
+    assert!(matches!(err, AcvpError::ResultNotSet { .. }));
+    assert_eq!(err.context().tcid, Some(1));
+}
+
+#[cfg(all(test, feature = "serde"))]
+#[test]
+fn test_serde() {
+    use dsa::{Dsa, DsaKeyOutput};
+    use hash::SecureHash;
+    use parser::{AcvpRequest, TestCase, TestResult};
+    use serde::de::DeserializeSeed;
+    use serialize::{Json, TestCaseSeed, TestGroupSeed};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 5, "algorithm": "SHA2-256", "revision": "1.0", "isSample": true,
+          "testGroups": [ { "tgId": 1, "testType": "AFT", "tests": [ { "tcId": 1, "msg": "00", "len": 8 } ] } ]
+        }
+    ]"#;
+
+    let mut req: AcvpRequest<SecureHash> =
+        serde_json::from_str(vector).expect("Failed to deserialize request");
+    assert_eq!(req.testgroups[0].tgdata.test_type, util::TestType::AFT);
+    let cached = serde_json::to_string(&req).expect("Failed to serialize request");
+    let restored: AcvpRequest<SecureHash> =
+        serde_json::from_str(&cached).expect("Failed to restore request");
+    assert_eq!(restored, req);
+
+    let tgdata = serde_json::to_value(&req.testgroups[0].tgdata).expect("Failed to serialize");
+    assert_eq!(tgdata["algorithm"]["Hash"], "SHA256");
+
+    req.testgroups[0].tests[0]
+        .set_result(vec![0u8; 32])
+        .expect("Failed to set result");
+    let resp = serde_json::to_value(Json(req.get_result().expect("No result")))
+        .expect("Failed to serialize response");
+    assert_eq!(resp[1]["testGroups"][0]["tests"][0]["tcId"], 1);
+
+    // Results survive a round trip, for requests, tests and test cases.
+    let cached = serde_json::to_string(&req).expect("Failed to serialize request");
+    let restored: AcvpRequest<SecureHash> =
+        serde_json::from_str(&cached).expect("Failed to restore request");
+    assert_eq!(restored.dump_result().unwrap(), req.dump_result().unwrap());
+    let test = &req.testgroups[0].tests[0];
+    let cached = serde_json::to_string(test).expect("Failed to serialize test");
+    let restored = TestCaseSeed::<SecureHash>::new(&test.tgdata)
+        .deserialize(&mut serde_json::Deserializer::from_str(&cached))
+        .expect("Failed to restore test");
+    assert_eq!(restored.dump_result().unwrap(), test.dump_result().unwrap());
+    let cached = serde_json::to_string(&test.test).expect("Failed to serialize test case");
+    let restored: SecureHash = serde_json::from_str(&cached).expect("Failed to restore test case");
+    assert_eq!(restored, test.test);
+
+    let err =
+        serde_json::from_str::<AcvpRequest<SecureHash>>(&vector.replace(r#""msg": "00", "#, ""))
+            .expect_err("Missing msg accepted");
+    assert!(err.to_string().contains("'msg'"));
+    let err =
+        serde_json::from_str::<AcvpRequest<SecureHash>>(&vector.replace(r#""tcId": 1, "#, ""))
+            .expect_err("Missing tcId accepted");
+    assert!(err.to_string().contains("missing field `tcId`"));
+
+    // Group level results are kept as well.
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 17, "algorithm": "DSA", "mode": "keyGen", "revision": "FIPS186-4",
+          "isSample": true,
+          "testGroups": [ { "tgId": 1, "testType": "AFT", "l": 2048, "n": 224,
+                            "tests": [ { "tcId": 1 } ] } ]
+        }
+    ]"#;
+    let mut req: AcvpRequest<Dsa> =
+        serde_json::from_str(vector).expect("Failed to deserialize request");
+    req.testgroups[0].tests[0]
+        .set_result(DsaKeyOutput::new(
+            vec![1],
+            vec![2],
+            vec![3],
+            vec![4],
+            vec![5],
+        ))
+        .expect("Failed to set result");
+    let cached = serde_json::to_string(&req.testgroups[0]).expect("Failed to serialize group");
+    assert!(cached.contains(r#""groupResult":{"p":"01","q":"02","g":"03"}"#));
+    let restored = TestGroupSeed::<Dsa>::new(req.alg_type)
+        .deserialize(&mut serde_json::Deserializer::from_str(&cached))
+        .expect("Failed to restore group");
+    assert_eq!(
+        restored.dump_result().unwrap(),
+        req.testgroups[0].dump_result().unwrap()
+    );
+    let cached = serde_json::to_string(&req).expect("Failed to serialize request");
+    let restored: AcvpRequest<Dsa> =
+        serde_json::from_str(&cached).expect("Failed to restore request");
+    assert_eq!(restored.dump_result().unwrap(), req.dump_result().unwrap());
+    assert_eq!(restored.mode, "keyGen");
+}
+
+#[cfg(test)]
+#[test]
+fn test_stream() {
+    use dsa::{Dsa, DsaKeyOutput};
+    use parser::{AcvpRequest, TestResult};
+    use stream::{RequestReader, ResponseWriter};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 6, "algorithm": "DSA", "mode": "keyGen", "revision": "FIPS186-4", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "l": 2048, "n": 224,
+              "tests": [ { "tcId": 1 }, { "tcId": 2 } ] },
+            { "tgId": 2, "testType": "AFT", "l": 3072, "n": 256, "tests": [ { "tcId": 3 } ],
+              "comment": "trailing \"}]\" field" },
+            { "tgId": 3, "testType": "AFT", "l": 2048, "n": 256, "tests": [] }
+          ]
+        }
+    ]"#;
+    let out =
+        |tcid: u32| DsaKeyOutput::new(vec![0xa], vec![0xb], vec![0xc], vec![tcid as u8], vec![0xe]);
+
+    let mut reader =
+        RequestReader::<Dsa, _>::new(vector.as_bytes()).expect("Failed to read header");
+    assert_eq!(reader.header.vsid, 6);
+    let mut writer = ResponseWriter::new(Vec::new(), &reader.header).expect("Failed to write");
+    let mut tcids = Vec::new();
+    while let Some(group) = reader.next_group().expect("Failed to read group") {
+        assert!(group.tests.is_empty());
+        writer.begin_group(group.tgid).expect("Failed to write");
+        while let Some(mut test) = reader.next_test().expect("Failed to read test") {
+            tcids.push(test.tcid);
+            test.set_result(out(test.tcid))
+                .expect("Failed to set result");
+            writer.write_test(&test).expect("Failed to write");
+        }
+    }
+    assert_eq!(tcids, vec![1, 2, 3]);
+    let streamed = String::from_utf8(writer.finish().expect("Failed to finish")).unwrap();
+
+    let mut req = AcvpRequest::<Dsa>::new(vector).expect("Failed to parse request");
+    for tg in &mut req.testgroups {
+        for test in &mut tg.tests {
+            test.set_result(out(test.tcid))
+                .expect("Failed to set result");
+        }
+    }
+    assert_eq!(streamed, req.dump_result().expect("Failed dump"));
+    assert_eq!(
+        json::parse(&streamed).expect("Bad JSON")[1]["mode"],
+        "keyGen"
+    );
+
+    let mut reader =
+        RequestReader::<Dsa, _>::new(vector.as_bytes()).expect("Failed to read header");
+    reader.next_group().expect("Failed to read group");
+    let group = reader
+        .next_group()
+        .expect("Failed to skip group")
+        .expect("No group");
+    assert_eq!(group.tgid, 2);
+    assert_eq!(
+        reader
+            .next_test()
+            .expect("Failed to read test")
+            .map(|t| t.tcid),
+        Some(3)
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_attach_response() {
+    use dsa::{Dsa, DsaKeyOutput};
+    use parser::{AcvpRequest, TestResult};
+    use response::AcvpResponse;
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 7, "algorithm": "DSA", "mode": "keyGen", "revision": "FIPS186-4", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "l": 2048, "n": 224,
+              "tests": [ { "tcId": 1 }, { "tcId": 2 } ] }
+          ]
+        }
+    ]"#;
+
+    let mut req = AcvpRequest::<Dsa>::new(vector).expect("Failed to parse request");
+    for test in &mut req.testgroups[0].tests {
+        let out = DsaKeyOutput::new(vec![0xa], vec![0xb], vec![0xc], vec![0xd], vec![0xe]);
+        test.set_result(out).expect("Failed to set result");
+    }
+    let dumped = req.dump_result().expect("Failed dump");
+
+    let resp = AcvpResponse::new(&dumped).expect("Failed to parse response");
+    assert_eq!(resp.find_group(1).map(|g| g.tests.len()), Some(2));
+    let mut loaded = AcvpRequest::<Dsa>::new(vector).expect("Failed to parse request");
+    assert!(loaded.get_result().is_err());
+    loaded
+        .attach_response(&resp)
+        .expect("Failed to attach response");
+    assert_eq!(loaded.dump_result().expect("Failed dump"), dumped);
+
+    let out = DsaKeyOutput::new(vec![0xa], vec![0xb], vec![0xc], vec![0xd], vec![0xf]);
+    loaded.testgroups[0].tests[1]
+        .set_result(out)
+        .expect("Failed to set result");
+    let res = loaded.get_result().expect("Failed to get result");
+    assert_eq!(res[1]["testGroups"][0]["tests"][1]["y"], "0F");
+
+    let other = AcvpResponse::new(&dumped.replace(r#""tcId":2"#, r#""tcId":9"#))
+        .expect("Failed to parse response");
+    let err = loaded
+        .attach_response(&other)
+        .expect_err("Unknown tcId accepted");
+    assert_eq!(err.context().tcid, Some(9));
+}
+
+#[cfg(test)]
+#[test]
+fn test_verify_expected() {
+    use hash::SecureHash;
+    use parser::{AcvpRequest, TestResult};
+    use response::AcvpResponse;
+    use verify::Verdict;
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 8, "algorithm": "SHA2-256", "revision": "1.0", "isSample": true,
+          "testGroups": [ { "tgId": 1, "testType": "AFT",
+            "tests": [ { "tcId": 1, "msg": "00", "len": 8 }, { "tcId": 2, "msg": "01", "len": 8 },
+                       { "tcId": 3, "msg": "02", "len": 8 } ] } ]
+        }
+    ]"#;
+    let md = "6E340B9CFFB37A989CA544E6BB780A2C78901D3FB33738768511A30617AFA01D";
+    let projection = vector
+        .replace(
+            r#""msg": "00", "len": 8"#,
+            &format!(r#""msg": "00", "len": 8, "md": "{}""#, md),
+        )
+        .replace(
+            r#""msg": "01", "len": 8"#,
+            &format!(
+                r#""msg": "01", "len": 8, "md": "{}""#,
+                md.to_ascii_lowercase()
+            ),
+        );
+    let expected = AcvpResponse::new(&projection).expect("Failed to parse expected results");
+
+    let mut req = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    let mut bad = hex::decode(md).unwrap();
+    bad[20] ^= 1;
+    req.testgroups[0].tests[0]
+        .set_result(bad)
+        .expect("Failed to set result");
+    req.testgroups[0].tests[1]
+        .set_result(hex::decode(md).unwrap())
+        .expect("Failed to set result");
+
+    let res = req.verify(&expected).expect("Failed to verify");
+    assert!(!res.passed());
+    assert!(
+        matches!(&res.tests[0].verdict, Verdict::Fail(m) if m.len() == 1 && m[0].field == "md")
+    );
+    assert!(res.tests[1].passed());
+    assert_eq!(res.tests[2].verdict, Verdict::NoResult);
+    let report = res.to_string();
+    assert!(report.contains("first difference at character 41"));
+    println!("{}", report);
+}
+
+#[cfg(test)]
+#[test]
+fn test_verify_fields() {
+    use dsa::{Dsa, DsaKeyOutput};
+    use msgauth::{AeadOutput, MsgAuth};
+    use parser::{AcvpRequest, TestResult};
+    use response::AcvpResponse;
+    use verify::Verdict;
+
+    // Big integers are reported without leading zeros.
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 15, "algorithm": "DSA", "mode": "keyGen", "revision": "FIPS186-4",
+          "isSample": true,
+          "testGroups": [ { "tgId": 1, "testType": "AFT", "l": 2048, "n": 224,
+                            "tests": [ { "tcId": 1 } ] } ]
+        }
+    ]"#;
+    let expected = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 15, "testGroups": [ { "tgId": 1, "p": "000A", "q": "0B", "g": "0C",
+                                        "tests": [ { "tcId": 1, "x": "00000D", "y": "0E" } ] } ] }
+    ]"#;
+    let expected = AcvpResponse::new(expected).expect("Failed to parse expected results");
+    let mut req = AcvpRequest::<Dsa>::new(vector).expect("Failed to parse request");
+    req.testgroups[0].tests[0]
+        .set_result(DsaKeyOutput::new(
+            vec![0xa],
+            vec![0xb],
+            vec![0xc],
+            vec![0xd],
+            vec![0xe],
+        ))
+        .expect("Failed to set result");
+    assert!(req.verify(&expected).expect("Failed to verify").passed());
+
+    // An expected field missing from the result is a mismatch, expected
+    // fields which are test inputs are not.
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 16, "algorithm": "ACVP-AES-GCM", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "direction": "decrypt", "ivGen": "external",
+              "ivLen": 96, "payloadLen": 0, "aadLen": 0, "tagLen": 128,
+              "tests": [ { "tcId": 1, "key": "00000000000000000000000000000000", "ct": "",
+                           "aad": "", "iv": "000000000000000000000000",
+                           "tag": "00000000000000000000000000000000" } ] } ]
+        }
+    ]"#;
+    let expected = vector.replace(
+        r#""tag": "00000000000000000000000000000000""#,
+        r#""tag": "00000000000000000000000000000000", "testPassed": false"#,
+    );
+    let expected = AcvpResponse::new(&expected).expect("Failed to parse expected results");
+    let mut req = AcvpRequest::<MsgAuth>::new(vector).expect("Failed to parse request");
+    req.testgroups[0].tests[0]
+        .set_result(AeadOutput::decrypt(Vec::new()))
+        .expect("Failed to set result");
+    let res = req.verify(&expected).expect("Failed to verify");
+    let mismatches = match &res.tests[0].verdict {
+        Verdict::Fail(m) => m,
+        verdict => panic!("Unexpected verdict {:?}", verdict),
+    };
+    let fields: Vec<(&str, bool, bool)> = mismatches
+        .iter()
+        .map(|m| (m.field.as_str(), m.expected.is_some(), m.actual.is_some()))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![("pt", false, true), ("testPassed", true, false)]
+    );
+    println!("{}", res);
+
+    req.testgroups[0].tests[0]
+        .set_result(AeadOutput::DecryptFail)
+        .expect("Failed to set result");
+    assert!(req.verify(&expected).expect("Failed to verify").passed());
+}
+
+#[cfg(test)]
+#[test]
+fn test_validate_results() {
+    use hash::SecureHash;
+    use parser::{AcvpRequest, TestResult};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 9, "algorithm": "SHA2-256", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT",
+              "tests": [ { "tcId": 1, "msg": "00", "len": 8 }, { "tcId": 2, "msg": "01", "len": 8 },
+                         { "tcId": 3, "msg": "02", "len": 8 } ] },
+            { "tgId": 2, "testType": "MCT",
+              "tests": [ { "tcId": 4, "msg": "03", "len": 8 } ] } ]
+        }
+    ]"#;
+    let mut req = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    req.testgroups[0].tests[0]
+        .set_result(vec![0u8; 32])
+        .expect("Failed to set result");
+    req.testgroups[0].tests[1]
+        .set_result(vec![0u8; 31])
+        .expect("Failed to set result");
+    let mut mct = vec![vec![0u8; 32]; 99];
+    mct[5] = vec![0u8; 20];
+    req.testgroups[1].tests[0]
+        .set_result(mct)
+        .expect("Failed to set result");
+
+    let report = req.validate();
+    assert!(!report.is_ok());
+    assert_eq!(report.unanswered, vec![(1, 3)]);
+    let issues: Vec<String> = report.issues.iter().map(|i| i.to_string()).collect();
+    assert_eq!(
+        issues,
+        vec![
+            "tgId 1 tcId 2: md is 31 bytes, expected 32",
+            "tgId 2 tcId 4: resultsArray has 99 entries, expected 100",
+            "tgId 2 tcId 4: resultsArray[5].md is 20 bytes, expected 32",
+        ]
+    );
+    println!("{}", report);
+
+    req.testgroups[0].tests[1]
+        .set_result(vec![0u8; 32])
+        .expect("Failed to set result");
+    req.testgroups[0].tests[2]
+        .set_result(vec![0u8; 32])
+        .expect("Failed to set result");
+    req.testgroups[1].tests[0]
+        .set_result(vec![vec![0u8; 32]; 100])
+        .expect("Failed to set result");
+    assert!(req.validate().is_ok());
+
+    // TDES Monte Carlo tests report 400 iterations rather than 100.
+    use blkcipher::{BlkCipherMCTOutput, BlockCipher};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 10, "algorithm": "ACVP-TDES-ECB", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "MCT", "direction": "encrypt", "keyingOption": 1,
+              "tests": [ { "tcId": 1, "key1": "0123456789ABCDEF", "key2": "23456789ABCDEF01",
+                           "key3": "456789ABCDEF0123", "pt": "4E6F772069732074" } ] } ]
+        }
+    ]"#;
+    let mut req = AcvpRequest::<BlockCipher>::new(vector).expect("Failed to parse request");
+    let out = BlkCipherMCTOutput::new_tdes(
+        vec![0u8; 8],
+        vec![0u8; 8],
+        vec![0u8; 8],
+        Vec::new(),
+        vec![0u8; 8],
+        vec![0u8; 8],
+    );
+    req.testgroups[0].tests[0]
+        .set_result(vec![out.clone(); 100])
+        .expect("Failed to set result");
+    let issues: Vec<String> = req
+        .validate()
+        .issues
+        .iter()
+        .map(|i| i.to_string())
+        .collect();
+    assert_eq!(
+        issues,
+        vec!["tgId 1 tcId 1: resultsArray has 100 entries, expected 400"]
+    );
+    req.testgroups[0].tests[0]
+        .set_result(vec![out; 400])
+        .expect("Failed to set result");
+    assert!(req.validate().is_ok());
+}
+
+#[cfg(test)]
+#[test]
+fn test_journal_resume() {
+    use hash::SecureHash;
+    use journal::Journal;
+    use parser::AcvpRequest;
+    use std::io::Write;
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 10, "algorithm": "SHA2-256", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT",
+              "tests": [ { "tcId": 1, "msg": "00", "len": 8 }, { "tcId": 2, "msg": "01", "len": 8 } ] },
+            { "tgId": 2, "testType": "AFT",
+              "tests": [ { "tcId": 3, "msg": "02", "len": 8 } ] } ]
+        }
+    ]"#;
+    let path = std::env::temp_dir().join(format!("acvp-journal-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut req = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    let mut journal = Journal::open(&path).expect("Failed to open journal");
+    let vsid = req.vsid;
+    let tg = &mut req.testgroups[0];
+    journal
+        .set_result(vsid, tg.tgid, &mut tg.tests[1], vec![0xaau8; 32])
+        .expect("Failed to record result");
+    drop(journal);
+    // Simulate a crash in the middle of writing an entry.
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    file.write_all(br#"{"vsId":10,"tgId":2,"tc"#).unwrap();
+    drop(file);
+
+    let mut req = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    assert_eq!(req.resume(&path).expect("Failed to resume"), 1);
+    let remaining: Vec<(u32, u32)> = req.remaining_tests().map(|(g, t)| (g, t.tcid)).collect();
+    assert_eq!(remaining, vec![(1, 1), (2, 3)]);
+
+    let mut journal = Journal::open(&path).expect("Failed to reopen journal");
+    for (tgid, test) in req.remaining_tests() {
+        journal
+            .set_result(vsid, tgid, test, vec![0x55u8; 32])
+            .expect("Failed to record result");
+    }
+    assert_eq!(Journal::entries(&path).unwrap().len(), 3);
+
+    let mut resumed = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    assert_eq!(resumed.resume(&path).expect("Failed to resume"), 3);
+    assert_eq!(resumed.remaining_tests().count(), 0);
+    assert_eq!(resumed.dump_result().unwrap(), req.dump_result().unwrap());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(test)]
+#[test]
+fn test_parallel_execution() {
+    use execute::Executor;
+    use hash::SecureHash;
+    use parser::{AcvpRequest, TestCase, TestResult};
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 11, "algorithm": "SHA2-256", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT",
+              "tests": [ { "tcId": 1, "msg": "00", "len": 8 }, { "tcId": 2, "msg": "01", "len": 8 },
+                         { "tcId": 3, "msg": "02", "len": 8 } ] },
+            { "tgId": 2, "testType": "AFT",
+              "tests": [ { "tcId": 4, "msg": "03", "len": 8 }, { "tcId": 5, "msg": "04", "len": 8 } ] } ]
+        }
+    ]"#;
+    let mut req = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    req.testgroups[1].tests[1]
+        .set_result(vec![0xffu8; 32])
+        .expect("Failed to set result");
+    Executor::new(3)
+        .run(&mut req, |test| Ok(vec![test.test.msg[0]; 32]))
+        .expect("Failed to run tests");
+    for tg in &req.testgroups {
+        for test in &tg.tests {
+            let md = if test.tcid == 5 {
+                0xff
+            } else {
+                test.tcid as u8 - 1
+            };
+            assert_eq!(
+                test.get_result().unwrap()["md"],
+                hex::encode_upper([md; 32]).as_str()
+            );
+        }
+    }
+
+    let mut req = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    let err = Executor::new(2)
+        .run(&mut req, |test| match test.tcid {
+            2 | 4 => Err(AcvpError::invalid("IUT failure")),
+            _ => Ok(vec![0u8; 32]),
+        })
+        .expect_err("Failure was not reported");
+    assert_eq!(err.context().tgid, Some(1));
+    assert_eq!(err.context().tcid, Some(2));
+
+    let mut req = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    Executor::default()
+        .run_groups(&mut req, |tg| {
+            for test in &mut tg.tests {
+                test.set_result(vec![tg.tgid as u8; 32])?;
+            }
+            Ok(())
+        })
+        .expect("Failed to run test groups");
+    assert!(req.validate().is_ok());
+}
+
+#[cfg(test)]
+#[test]
+fn test_parallel_journal() {
+    use execute::Executor;
+    use hash::SecureHash;
+    use journal::Journal;
+    use parser::AcvpRequest;
+    use std::sync::Mutex;
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 13, "algorithm": "SHA2-256", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT",
+              "tests": [ { "tcId": 1, "msg": "00", "len": 8 }, { "tcId": 2, "msg": "01", "len": 8 },
+                         { "tcId": 3, "msg": "02", "len": 8 } ] },
+            { "tgId": 2, "testType": "AFT",
+              "tests": [ { "tcId": 4, "msg": "03", "len": 8 }, { "tcId": 5, "msg": "04", "len": 8 } ] } ]
+        }
+    ]"#;
+    let path = std::env::temp_dir().join(format!(
+        "acvp-parallel-journal-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+
+    // The run is interrupted by a failure of tcId 4, the other tests are
+    // done and journaled.
+    let mut req = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    let mut journal = Journal::open(&path).expect("Failed to open journal");
+    let err = Executor::new(1)
+        .run_journaled(&mut req, &mut journal, |test| match test.tcid {
+            4 => Err(AcvpError::invalid("IUT failure")),
+            _ => Ok(vec![test.test.msg[0]; 32]),
+        })
+        .expect_err("Failure was not reported");
+    assert_eq!(err.context().tcid, Some(4));
+    drop(journal);
+    let mut journaled: Vec<u32> = Journal::entries(&path)
+        .unwrap()
+        .iter()
+        .map(|e| e.tcid)
+        .collect();
+    journaled.sort();
+    assert_eq!(journaled, vec![1, 2, 3]);
+
+    // Only the tests missing from the journal are run on resume.
+    let mut req = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    assert_eq!(req.resume(&path).expect("Failed to resume"), 3);
+    let mut journal = Journal::open(&path).expect("Failed to reopen journal");
+    let ran = Mutex::new(Vec::new());
+    Executor::new(3)
+        .run_journaled(&mut req, &mut journal, |test| {
+            ran.lock().unwrap().push(test.tcid);
+            Ok(vec![test.test.msg[0]; 32])
+        })
+        .expect("Failed to run tests");
+    let mut ran = ran.into_inner().unwrap();
+    ran.sort();
+    assert_eq!(ran, vec![4, 5]);
+
+    let mut resumed = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    assert_eq!(resumed.resume(&path).expect("Failed to resume"), 5);
+    assert_eq!(resumed.remaining_tests().count(), 0);
+    assert_eq!(resumed.dump_result().unwrap(), req.dump_result().unwrap());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(test)]
+#[test]
+fn test_backend_runner() {
+    use backend::Backend;
+    use blkcipher::BlockCipher;
+    use dispatch::AnyRequest;
+    use execute::Executor;
+    use msgauth::{MsgAuth, MsgAuthOutput};
+    use parser::{AcvpRequest, TestCase};
+    use util::{AeadMode, BlockCipherAlg, CipherMode};
+
+    // Toy module: XOR "cipher" and a tag made of the first key byte.
+    struct Toy;
+    impl Backend for Toy {
+        fn encrypt(
+            &self,
+            _alg: BlockCipherAlg,
+            _mode: CipherMode,
+            key: &[u8],
+            _iv: &[u8],
+            input: &[u8],
+        ) -> AcvpResult<Vec<u8>> {
+            Ok(input
+                .iter()
+                .zip(key.iter().cycle())
+                .map(|(i, k)| i ^ k)
+                .collect())
+        }
+
+        fn aead_encrypt(
+            &self,
+            _alg: BlockCipherAlg,
+            _mode: AeadMode,
+            test: &MsgAuth,
+        ) -> AcvpResult<MsgAuthOutput> {
+            Ok(MsgAuthOutput {
+                out: test.msg.clone(),
+                tag: vec![test.key[0] ^ test.iv[0]; test.taglen],
+            })
+        }
+
+        fn aead_decrypt(
+            &self,
+            _alg: BlockCipherAlg,
+            _mode: AeadMode,
+            test: &MsgAuth,
+        ) -> AcvpResult<Option<Vec<u8>>> {
+            Ok(test
+                .tag
+                .iter()
+                .all(|&b| b == 0x58)
+                .then(|| test.msg.clone()))
+        }
+
+        fn random(&self, buf: &mut [u8]) -> AcvpResult<()> {
+            buf.fill(0x11);
+            Ok(())
+        }
+    }
+
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 12, "algorithm": "ACVP-AES-GCM", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "direction": "encrypt", "ivGen": "internal",
+              "ivGenMode": "8.2.2", "ivLen": 96, "payloadLen": 0, "aadLen": 0, "tagLen": 128,
+              "tests": [ { "tcId": 1, "key": "01000000000000000000000000000000", "pt": "", "aad": "" } ]
+            },
+            { "tgId": 2, "testType": "AFT", "direction": "decrypt", "ivGen": "external",
+              "ivLen": 96, "payloadLen": 0, "aadLen": 0, "tagLen": 128,
+              "tests": [
+                { "tcId": 2, "key": "00000000000000000000000000000000", "ct": "", "aad": "",
+                  "iv": "000000000000000000000000", "tag": "58585858585858585858585858585858" },
+                { "tcId": 3, "key": "00000000000000000000000000000000", "ct": "", "aad": "",
+                  "iv": "000000000000000000000000", "tag": "00000000000000000000000000000000" }
+              ]
+            }
+          ]
+        }
+    ]"#;
+    let mut req = AnyRequest::new(vector).expect("Failed to parse request");
+    req.run(&Toy).expect("Failed to run request");
+    let res = req.get_result().expect("Failed to get result");
+    let tgs = &res[1]["testGroups"];
+    assert_eq!(tgs[0]["tests"][0]["iv"], "111111111111111111111111");
+    assert_eq!(
+        tgs[0]["tests"][0]["tag"],
+        "10101010101010101010101010101010"
+    );
+    assert_eq!(tgs[1]["tests"][0]["pt"], "");
+    assert_eq!(tgs[1]["tests"][1]["testPassed"], false);
+
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 13, "algorithm": "ACVP-AES-ECB", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "direction": "encrypt", "keyLen": 128,
+              "tests": [ { "tcId": 1, "key": "0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F",
+                           "pt": "F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0" } ] },
+            { "tgId": 2, "testType": "AFT", "direction": "decrypt", "keyLen": 128,
+              "tests": [ { "tcId": 2, "key": "0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F",
+                           "ct": "F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0" } ] } ]
+        }
+    ]"#;
+    let mut req = AcvpRequest::<BlockCipher>::new(vector).expect("Failed to parse request");
+    let err = Executor::new(2)
+        .run_backend(&mut req, &Toy)
+        .expect_err("Missing primitive was not reported");
+    assert!(matches!(err, AcvpError::UnsupportedAlgorithm { .. }));
+    assert_eq!(err.context().tcid, Some(2));
+    assert_eq!(
+        req.testgroups[0].tests[0].get_result().unwrap()["ct"],
+        "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_mct_drivers() {
+    use blkcipher::BlockCipher;
+    use parser::{AcvpRequest, TestResult};
+    use util::{AcvpAlgorithm, BlockCipherAlg, CipherMode, Direction, HashAlg};
+
+    // Toy digest: the byte sum of the message, repeated.
+    let mut calls = 0;
+    let mds = hash::mct(HashAlg::SHA256, &[1; 32], |msg| {
+        calls += 1;
+        assert_eq!(msg.len(), 96);
+        Ok(vec![msg.iter().fold(0u8, |s, b| s.wrapping_add(*b)); 32])
+    })
+    .expect("Failed to run SHA-2 MCT");
+    assert_eq!((mds.len(), calls), (100, 100_000));
+    let mds = hash::mct(HashAlg::SHA3_256, &[1; 32], |msg| {
+        assert_eq!(msg.len(), 32);
+        Ok(msg.iter().map(|b| b.wrapping_add(1)).collect())
+    })
+    .expect("Failed to run SHA-3 MCT");
+    assert_eq!(mds[0], vec![0xe9; 32]);
+    assert_eq!(mds[99], vec![(1 + 100_000 % 256) as u8; 32]);
+    assert!(hash::mct(HashAlg::SHAKE128, &[], |m| Ok(m.to_vec())).is_err());
+
+    let alg = AcvpAlgorithm::alg_type("ACVP-TDES-CFB64", "").expect("Failed");
+    assert_eq!(
+        alg,
+        AcvpAlgorithm::BlockCipher(BlockCipherAlg::TDES, CipherMode::CFB64)
+    );
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 14, "algorithm": "ACVP-AES-CFB8", "revision": "1.0", "isSample": true,
+          "testGroups": [ { "tgId": 1, "testType": "MCT", "direction": "encrypt", "keyLen": 128,
+            "tests": [ { "tcId": 1, "key": "00000000000000000000000000000000",
+                         "iv": "000102030405060708090A0B0C0D0E0F", "pt": "FF" } ] } ]
+        }
+    ]"#;
+    let mut req = AcvpRequest::<BlockCipher>::new(vector).expect("Failed to parse request");
+    let test = &mut req.testgroups[0].tests[0];
+    // Toy CFB8: the output is the input XOR the first IV byte.
+    let res = test
+        .test
+        .mct(|_key, iv, input| {
+            assert_eq!((iv.len(), input.len()), (16, 1));
+            Ok(vec![input[0] ^ iv[0]])
+        })
+        .expect("Failed to run CFB8 MCT");
+    assert_eq!(res.len(), 100);
+    assert_eq!(res[0].iv, (0..16).collect::<Vec<u8>>());
+    assert_eq!(res[0].inp, vec![0xff]);
+    assert_eq!(res[1].iv.len(), 16);
+    test.set_result(res).expect("Failed to set result");
+    assert!(req.validate().is_ok());
+
+    // Bad AES key lengths are rejected rather than panicking.
+    for key in [&[][..], &[0u8; 8][..], &[0u8; 20][..]] {
+        let res = blkcipher::mct(
+            BlockCipherAlg::AES,
+            CipherMode::ECB,
+            Direction::Encrypt,
+            key,
+            &[],
+            &[0u8; 16],
+            |_key, _iv, input| Ok(input.to_vec()),
+        );
+        assert!(matches!(res, Err(AcvpError::Invalid { .. })));
+    }
+
+    // TDES with keying option 3 keeps three equal keys with odd parity.
+    let res = blkcipher::mct(
+        BlockCipherAlg::TDES,
+        CipherMode::ECB,
+        Direction::Encrypt,
+        &[0x01; 24],
+        &[],
+        &[0x5a; 8],
+        |key, _iv, input| Ok(input.iter().zip(key).map(|(i, k)| i ^ k ^ 0x33).collect()),
+    )
+    .expect("Failed to run TDES MCT");
+    assert_eq!(res.len(), 400);
+    for r in &res {
+        assert!(r.iv.is_empty() && r.key1 == r.key2 && r.key1 == r.key3);
+        assert!(r.key1.iter().all(|b| b.count_ones() % 2 == 1));
+    }
+    assert!(blkcipher::mct(
+        BlockCipherAlg::AES,
+        CipherMode::CTR,
+        Direction::Encrypt,
+        &[0; 16],
+        &[0; 16],
+        &[0; 16],
+        |_, _, input| Ok(input.to_vec()),
+    )
+    .is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_mct_known_answers() {
+    use backend::Backend;
+    use hash::SecureHash;
+    use parser::{AcvpRequest, TestCase, TestResult};
+    use util::{BlockCipherAlg, CipherMode, Direction, HashAlg};
+
+    // The reference implementations against the FIPS 180-4, FIPS 202 and
+    // FIPS 197 examples.
+    assert_eq!(
+        hex::encode(refimpl::sha256(b"abc")),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(
+        hex::encode(refimpl::sha3_256(b"abc")),
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+    );
+    assert_eq!(
+        hex::encode(refimpl::shake128(b"", 32)),
+        "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+    );
+    let key: Vec<u8> = (0..16).collect();
+    assert_eq!(
+        hex::encode(refimpl::aes_encrypt(
+            &key,
+            &hex::decode("00112233445566778899aabbccddeeff").unwrap()
+        )),
+        "69c4e0d86a7b0430d8cdb78070b4c55a"
+    );
+
+    // SHA256Monte.rsp of the CAVP SHA test vectors.
+    let seed =
+        hex::decode("6d1e72ad03ddeb5de891e572e2396f8da015d899ef0e79503152d6010a3fe691").unwrap();
+    let mds = hash::mct(HashAlg::SHA256, &seed, |msg| Ok(refimpl::sha256(msg)))
+        .expect("Failed to run SHA-256 MCT");
+    assert_eq!(
+        hex::encode(&mds[0]),
+        "e93c330ae5447738c8aa85d71a6c80f2a58381d05872d26bdd39f1fcd4f2b788"
+    );
+    assert_eq!(
+        hex::encode(&mds[1]),
+        "2e78f8c8772ea7c9331d41ed3f9cdf27d8f514a99342ee766ee3b8b0d0b121c0"
+    );
+
+    // ECBMCT128.rsp of the CAVP AESAVS vectors.
+    let res = blkcipher::mct(
+        BlockCipherAlg::AES,
+        CipherMode::ECB,
+        Direction::Encrypt,
+        &hex::decode("139a35422f1d61de3c91787fe0507afd").unwrap(),
+        &[],
+        &hex::decode("b9145a768b7dc489a096b546f43b231f").unwrap(),
+        |key, _iv, input| Ok(refimpl::aes_encrypt(key, input)),
+    )
+    .expect("Failed to run AES MCT");
+    assert_eq!(hex::encode(&res[0].out), "d7c3ffac9031238650901e157364c386");
+    assert_eq!(hex::encode(&res[1].key), "c459caeebf2c42586c01666a9334b97b");
+    assert_eq!(hex::encode(&res[1].out), "bc3637da2daf8fcf7c68bb28c143a0a4");
+
+    // SHA-3 and SHAKE digests cross-checked with Python's hashlib.
+    let seed: Vec<u8> = (0..32).collect();
+    let mds = hash::mct(HashAlg::SHA3_256, &seed, |msg| Ok(refimpl::sha3_256(msg)))
+        .expect("Failed to run SHA3-256 MCT");
+    assert_eq!(
+        hex::encode(&mds[0]),
+        "31e7e4baf824fe7f6337913f5442f33accf166a182500fd7e254e9b9e8244a04"
+    );
+    assert_eq!(
+        hex::encode(&mds[99]),
+        "830ae73ddb0987e4313536121121989818a24d5bbf31dc5b5348070643d34ffb"
+    );
+
+    struct Ref;
+    impl Backend for Ref {
+        fn xof(&self, alg: HashAlg, msg: &[u8], out_len: usize) -> AcvpResult<Vec<u8>> {
+            assert_eq!(alg, HashAlg::SHAKE128);
+            Ok(refimpl::shake128(msg, out_len))
+        }
+    }
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 18, "algorithm": "SHAKE-128", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "MCT", "minOutLen": 16, "maxOutLen": 1024,
+              "tests": [ { "tcId": 1, "msg": "000102030405060708090A0B0C0D0E0F", "len": 128 } ] },
+            { "tgId": 2, "testType": "VOT",
+              "tests": [ { "tcId": 2, "msg": "", "len": 0, "outLen": 256 },
+                         { "tcId": 3, "msg": "", "len": 0, "outputLen": 24 } ] } ]
+        }
+    ]"#;
+    let mut req = AcvpRequest::<SecureHash>::new(vector).expect("Failed to parse request");
+    assert_eq!(req.testgroups[0].tests[0].test.max_out_len, 1024);
+    assert_eq!(req.testgroups[1].tests[1].test.out_len, 24);
+    req.run(&Ref).expect("Failed to run SHAKE tests");
+    assert!(req.validate().is_ok());
+    let res = req.testgroups[0].tests[0].get_result().unwrap();
+    assert_eq!(res["resultsArray"].len(), 100);
+    assert_eq!(res["resultsArray"][0]["outLen"], 960);
+    assert_eq!(
+        res["resultsArray"][0]["md"].as_str().unwrap(),
+        "828F57105E53D2123ED6530DDC9F540A7D7CB65069C7F95A813D767A24D23C85865CAEA4B1DB006C\
+         892F3E1F52EF4EE1E22DC16743E7864F9C2EF4E22AF1697EE1BB7CCE64E1BD765102C2A7C75E8B7D\
+         FCAE36857E38087B6DD6C0C8DB7355FFABAA1DC84F3C204F7AA578A99AA27CC19BBBD4BABDFF26B8"
+    );
+    assert_eq!(res["resultsArray"][99]["outLen"], 656);
+    assert_eq!(
+        res["resultsArray"][99]["md"].as_str().unwrap(),
+        "B2FAA7E67AD3200EB9BDB4A6FF834EA3F122B4D54A7E1B738DF712CCB3E22D3741773E263E06549F\
+         B1FCBA2B3079133FDDA19C48971F67C79ECF419155F985AA5B7BEFFC3DBFCE5AAD3EF113B1C4FADE15B6"
+    );
+    assert_eq!(
+        req.testgroups[1].tests[0].get_result().unwrap()["md"],
+        "7F9C2BA4E88F827D616045507605853ED73B8093F6EFBC88EB1A6EACFA66EF26"
+    );
+    assert_eq!(
+        req.testgroups[1].tests[1].get_result().unwrap()["md"],
+        "7F9C2B"
+    );
+
+    let mut short = req.testgroups[1].tests[0].clone();
+    short
+        .set_result(vec![0u8; 31])
+        .expect("Failed to set result");
+    assert!(!short
+        .validate_result(&short.get_result().unwrap())
+        .is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn test_backend_families() {
+    use backend::Backend;
+    use conditioning::Conditioning;
+    use dispatch::AnyRequest;
+    use dsa::{Dsa, DsaKeyOutput};
+    use rsa::RsaPrimitive;
+    use safeprimes::SafePrimes;
+    use util::HashAlg;
+
+    // Toy module: reference SHA-256, an RSA primitive rejecting inputs of
+    // more than two bytes and fixed DSA domain parameters.
+    struct Toy;
+    impl Backend for Toy {
+        fn hash(&self, _alg: HashAlg, msg: &[u8]) -> AcvpResult<Vec<u8>> {
+            Ok(refimpl::sha256(msg))
+        }
+        fn rsa_primitive(&self, test: &RsaPrimitive) -> AcvpResult<Option<Vec<u8>>> {
+            Ok((test.input.len() <= 2).then(|| test.input.clone()))
+        }
+        fn safe_primes_key_ver(&self, test: &SafePrimes) -> AcvpResult<bool> {
+            Ok(test.x < test.y)
+        }
+        fn dsa_key_gen(&self, _test: &Dsa) -> AcvpResult<DsaKeyOutput> {
+            Ok(DsaKeyOutput::new(
+                vec![0xa],
+                vec![0xb],
+                vec![0xc],
+                vec![0xd],
+                vec![0xe],
+            ))
+        }
+        fn conditioning(&self, test: &Conditioning) -> AcvpResult<Vec<u8>> {
+            Ok(refimpl::sha256(&test.payload)[..test.output_len / 8].to_vec())
+        }
+    }
+
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 20, "algorithm": "SHA2-256", "revision": "1.0", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "LDT",
+              "tests": [ { "tcId": 1, "largeMsg": { "content": "616263", "contentLength": 24,
+                           "fullLength": 80, "expansionTechnique": "repeating" } } ] }
+          ]
+        }
+    ]"#;
+    let mut req = AnyRequest::new(vector).expect("Failed to parse request");
+    req.run(&Toy).expect("Failed to run request");
+    let res = req.get_result().expect("Failed to get result");
+    assert_eq!(
+        res[1]["testGroups"][0]["tests"][0]["md"],
+        hex::encode_upper(refimpl::sha256(b"abcabcabca"))
+    );
+    assert!(matches!(
+        AnyRequest::new(&vector.replace("repeating", "doubling")),
+        Err(AcvpError::UnsupportedAlgorithm { .. })
+    ));
+    let vector = vector.replace(r#""contentLength": 24"#, r#""contentLength": 16"#);
+    assert!(matches!(
+        AnyRequest::new(&vector),
+        Err(AcvpError::Invalid { .. })
+    ));
+
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 21, "algorithm": "RSA", "mode": "decryptionPrimitive", "revision": "Sp800-56Br2",
+          "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "modulo": 2048, "keyFormat": "standard",
+              "tests": [
+                { "tcId": 1, "n": "C0FFEE", "e": "010001", "d": "11", "c": "0ABC" },
+                { "tcId": 2, "n": "C0FFEE", "e": "010001", "d": "11", "c": "FFFFFF" }
+              ]
+            }
+          ]
+        }
+    ]"#;
+    let mut req = AnyRequest::new(vector).expect("Failed to parse request");
+    req.run(&Toy).expect("Failed to run request");
+    let res = req.get_result().expect("Failed to get result");
+    let tests = &res[1]["testGroups"][0]["tests"];
+    assert_eq!(tests[0]["pt"], "0ABC");
+    assert_eq!(tests[1]["testPassed"], false);
+
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 22, "algorithm": "safePrimes", "mode": "keyVer", "revision": "1.0",
+          "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "safePrimeGroup": "ffdhe2048",
+              "tests": [ { "tcId": 1, "x": "1A", "y": "2B" }, { "tcId": 2, "x": "2B", "y": "1A" } ] }
+          ]
+        }
+    ]"#;
+    let mut req = AnyRequest::new(vector).expect("Failed to parse request");
+    req.run(&Toy).expect("Failed to run request");
+    let res = req.get_result().expect("Failed to get result");
+    let tests = &res[1]["testGroups"][0]["tests"];
+    assert_eq!(tests[0]["testPassed"], true);
+    assert_eq!(tests[1]["testPassed"], false);
+
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 23, "algorithm": "DSA", "mode": "keyGen", "revision": "FIPS186-4", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "l": 2048, "n": 224,
+              "tests": [ { "tcId": 1 }, { "tcId": 2 } ] }
+          ]
+        }
+    ]"#;
+    let mut req = AnyRequest::new(vector).expect("Failed to parse request");
+    req.run(&Toy).expect("Failed to run request");
+    let res = req.get_result().expect("Failed to get result");
+    let tg = &res[1]["testGroups"][0];
+    assert_eq!(tg["g"], "0C");
+    assert_eq!(tg["tests"][1]["x"], "0D");
+
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 24, "algorithm": "ConditioningComponent", "mode": "Hash_DF",
+          "revision": "SP800-90B", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT", "hashAlg": "SHA2-256", "payloadLen": 24,
+              "outputLen": 128,
+              "tests": [ { "tcId": 1, "payload": "616263" } ] }
+          ]
+        }
+    ]"#;
+    let mut req = AnyRequest::new(vector).expect("Failed to parse request");
+    req.run(&Toy).expect("Failed to run request");
+    let res = req.get_result().expect("Failed to get result");
+    assert_eq!(
+        res[1]["testGroups"][0]["tests"][0]["output"],
+        "BA7816BF8F01CFEA414140DE5DAE2223"
+    );
+
+    // Families the module does not implement are reported as such.
+    let vector = r#"[
+        { "acvVersion": "1.0" },
+        { "vsId": 25, "algorithm": "Ascon-Hash256", "revision": "SP800-232", "isSample": true,
+          "testGroups": [
+            { "tgId": 1, "testType": "AFT",
+              "tests": [ { "tcId": 1, "msg": "616263", "len": 24 } ] }
+          ]
+        }
+    ]"#;
+    let mut req = AnyRequest::new(vector).expect("Failed to parse request");
+    let err = req
+        .run(&Toy)
+        .expect_err("Missing primitive was not reported");
+    assert!(matches!(err, AcvpError::UnsupportedAlgorithm { .. }));
+}
@@ -3,33 +3,62 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use std::str::FromStr;
-
 use json::JsonValue;
 
 use crate::{
     blkcipher::BlkCipherMCTOutput,
-    drbg::DrbgMode,
-    msgauth::MsgAuthOutput,
-    util::{AcvpAlgorithm, Direction, IVMode, TestType},
+    dispatch::AnyResult,
+    dsa::{DsaKeyOutput, DsaPQGOutput, DsaSigOutput},
+    ifc::IfcOutput,
+    msgauth::{AeadOutput, MsgAuthOutput},
+    safeprimes::SafePrimesKeyOutput,
+    util::{AcvpAlgorithm, TestType},
     AcvpError, AcvpResult,
 };
 
 pub trait TestGroup {
-    fn new(algorithm: &str, tgjson: &str) -> AcvpResult<Self>
+    fn new(algorithm: AcvpAlgorithm, tg: &JsonValue) -> AcvpResult<Self>
     where
         Self: Sized;
     fn dump(&self) -> String;
     fn pretty(&self) -> String;
 }
 
+/// Test group parameters of a test case family, parsed once per test group.
+///
+/// Fields required by the family are mandatory, a missing one fails the
+/// parsing of the test group.
+pub trait GroupData: Clone + std::fmt::Debug + PartialEq + Eq {
+    fn new(algorithm: AcvpAlgorithm, test_type: TestType, tg: &JsonValue) -> AcvpResult<Self>
+    where
+        Self: Sized;
+}
+
 pub trait TestCase {
-    fn new(test: &str, tgdata: &TestGroupData) -> AcvpResult<Self>
+    type GroupData: GroupData;
+
+    /// Construct the test case from its already parsed JSON object.
+    fn new(test: &JsonValue, tgdata: &Self::GroupData) -> AcvpResult<Self>
     where
         Self: Sized;
     fn get_result(&self) -> AcvpResult<JsonValue>;
+    /// Result fields which are reported once per test group rather than per
+    /// test case, e.g. domain parameters generated by the IUT.
+    fn get_group_result(&self) -> Option<JsonValue> {
+        None
+    }
     fn dump_result(&self) -> AcvpResult<String>;
     fn pretty_result(&self) -> AcvpResult<String>;
+    /// Set a result without naming its type, the result is dispatched to
+    /// the matching `TestResult` implementation of the test case.
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        Err(res.unsupported())
+    }
+    /// Check the shape of a result of this test case, e.g. its output
+    /// lengths, and describe every problem found.
+    fn validate_result(&self, _res: &JsonValue) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 pub trait TestResult<T> {
@@ -37,106 +66,166 @@ pub trait TestResult<T> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct TestGroupData {
-    pub algorithm: String,
-    pub test_type: TestType,
-    // For AEAD
-    pub taglen: usize,
-    pub payload_len: usize,
-    pub ivmode: IVMode,
-    pub ivlen: usize,
-    // For SKCipher
-    pub direction: Direction,
-    // For AKCipher
-    // pub hash_alg: String,
-    // pub sig_type: String,
-    // pub n: Vec<u8>,
-    // pub e: Vec<u8>,
-    // For DRBG
-    pub drbgmode: DrbgMode,
-    pub prediction_resistance: bool,
-    pub reseed: bool,
-    pub der_func: bool,
-    pub returned_bits_len: usize,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct AcvpTest<T> {
+pub struct AcvpTest<T: TestCase> {
     pub tcid: u32,
-    pub tgdata: TestGroupData,
+    pub tgdata: T::GroupData,
     pub test: T,
-    test_json: JsonValue,
+    pub(crate) test_json: JsonValue,
+    /// Result loaded from a response file, reported until a result is set.
+    pub(crate) stored_result: Option<JsonValue>,
 }
 
 impl<T: TestCase> TestCase for AcvpTest<T> {
-    fn new(test: &str, tgdata: &TestGroupData) -> AcvpResult<Self> {
-        let tc = match json::parse(test) {
-            Ok(tc) => tc,
-            Err(_e) => {
-                return Err(AcvpError {
-                    code: -libc::EINVAL,
-                    message: "Failed to parse testcase JSON".to_string(),
-                });
-            }
-        };
+    type GroupData = T::GroupData;
 
-        if !tc.has_key("tcId") {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "Required field tcID missing from testcase JSON".to_string(),
-            });
-        }
-        let tcid = crate::util::get_acvp_u32("tcId", &tc)?;
-        let test = T::new(test, tgdata)?;
-
-        Ok(AcvpTest {
-            tcid,
-            tgdata: tgdata.clone(),
-            test,
-            test_json: tc,
-        })
+    fn new(tc: &JsonValue, tgdata: &T::GroupData) -> AcvpResult<Self> {
+        Self::from_json(tc.clone(), tgdata)
     }
 
     fn get_result(&self) -> AcvpResult<JsonValue> {
-        self.test.get_result()
+        match (self.test.get_result(), &self.stored_result) {
+            (Err(AcvpError::ResultNotSet { .. }), Some(stored)) => Ok(stored.clone()),
+            (res, _) => res.map_err(|e| e.with_tcid(self.tcid)),
+        }
+    }
+
+    fn get_group_result(&self) -> Option<JsonValue> {
+        self.test.get_group_result()
     }
 
     fn dump_result(&self) -> AcvpResult<String> {
-        self.test.dump_result()
+        let res = self.get_result()?;
+        Ok(res.dump())
     }
 
     fn pretty_result(&self) -> AcvpResult<String> {
-        self.test.pretty_result()
+        let res = self.get_result()?;
+        Ok(res.pretty(3))
+    }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        self.test
+            .set_any_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
+    }
+
+    fn validate_result(&self, res: &JsonValue) -> Vec<String> {
+        self.test.validate_result(res)
     }
 }
 
-impl<T: TestResult<bool>> TestResult<bool> for AcvpTest<T> {
+impl<T: TestCase + TestResult<bool>> TestResult<bool> for AcvpTest<T> {
     fn set_result(&mut self, res: bool) -> AcvpResult<()> {
-        self.test.set_result(res)
+        self.test
+            .set_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
     }
 }
 
-impl<T: TestResult<Vec<u8>>> TestResult<Vec<u8>> for AcvpTest<T> {
+impl<T: TestCase + TestResult<Vec<u8>>> TestResult<Vec<u8>> for AcvpTest<T> {
     fn set_result(&mut self, res: Vec<u8>) -> AcvpResult<()> {
-        self.test.set_result(res)
+        self.test
+            .set_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
     }
 }
 
-impl<T: TestResult<Vec<Vec<u8>>>> TestResult<Vec<Vec<u8>>> for AcvpTest<T> {
+impl<T: TestCase + TestResult<Vec<Vec<u8>>>> TestResult<Vec<Vec<u8>>> for AcvpTest<T> {
     fn set_result(&mut self, res: Vec<Vec<u8>>) -> AcvpResult<()> {
-        self.test.set_result(res)
+        self.test
+            .set_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
     }
 }
 
-impl<T: TestResult<Vec<BlkCipherMCTOutput>>> TestResult<Vec<BlkCipherMCTOutput>> for AcvpTest<T> {
+impl<T: TestCase + TestResult<Vec<BlkCipherMCTOutput>>> TestResult<Vec<BlkCipherMCTOutput>>
+    for AcvpTest<T>
+{
     fn set_result(&mut self, res: Vec<BlkCipherMCTOutput>) -> AcvpResult<()> {
-        self.test.set_result(res)
+        self.test
+            .set_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
     }
 }
 
-impl<T: TestResult<MsgAuthOutput>> TestResult<MsgAuthOutput> for AcvpTest<T> {
+impl<T: TestCase + TestResult<MsgAuthOutput>> TestResult<MsgAuthOutput> for AcvpTest<T> {
     fn set_result(&mut self, res: MsgAuthOutput) -> AcvpResult<()> {
-        self.test.set_result(res)
+        self.test
+            .set_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
+    }
+}
+
+impl<T: TestCase + TestResult<AeadOutput>> TestResult<AeadOutput> for AcvpTest<T> {
+    fn set_result(&mut self, res: AeadOutput) -> AcvpResult<()> {
+        self.test
+            .set_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
+    }
+}
+
+impl<T: TestCase + TestResult<DsaPQGOutput>> TestResult<DsaPQGOutput> for AcvpTest<T> {
+    fn set_result(&mut self, res: DsaPQGOutput) -> AcvpResult<()> {
+        self.test
+            .set_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
+    }
+}
+
+impl<T: TestCase + TestResult<DsaKeyOutput>> TestResult<DsaKeyOutput> for AcvpTest<T> {
+    fn set_result(&mut self, res: DsaKeyOutput) -> AcvpResult<()> {
+        self.test
+            .set_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
+    }
+}
+
+impl<T: TestCase + TestResult<DsaSigOutput>> TestResult<DsaSigOutput> for AcvpTest<T> {
+    fn set_result(&mut self, res: DsaSigOutput) -> AcvpResult<()> {
+        self.test
+            .set_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
+    }
+}
+
+impl<T: TestCase + TestResult<SafePrimesKeyOutput>> TestResult<SafePrimesKeyOutput>
+    for AcvpTest<T>
+{
+    fn set_result(&mut self, res: SafePrimesKeyOutput) -> AcvpResult<()> {
+        self.test
+            .set_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
+    }
+}
+
+impl<T: TestCase + TestResult<IfcOutput>> TestResult<IfcOutput> for AcvpTest<T> {
+    fn set_result(&mut self, res: IfcOutput) -> AcvpResult<()> {
+        self.test
+            .set_result(res)
+            .map_err(|e| e.with_tcid(self.tcid))
+    }
+}
+
+impl<T: TestCase> AcvpTest<T> {
+    /// Construct the test case from its JSON object, which is kept for
+    /// `dump` without being copied.
+    pub(crate) fn from_json(tc: JsonValue, tgdata: &T::GroupData) -> AcvpResult<Self> {
+        if !tc.is_object() {
+            return Err(AcvpError::parse("Testcase must be a JSON object"));
+        }
+        if !tc.has_key("tcId") {
+            return Err(AcvpError::missing_field("tcId", "u32"));
+        }
+        let tcid = crate::util::get_acvp_u32("tcId", &tc)?;
+        let test = T::new(&tc, tgdata).map_err(|e| e.with_tcid(tcid))?;
+
+        Ok(AcvpTest {
+            tcid,
+            tgdata: tgdata.clone(),
+            test,
+            test_json: tc,
+            stored_result: None,
+        })
     }
 }
 
@@ -155,150 +244,121 @@ impl<T: Clone + TestCase> AcvpTest<T> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct AcvpTestGroup<T> {
-    test_type: TestType,
-    tgid: u32,
+pub struct AcvpTestGroup<T: TestCase> {
+    pub test_type: TestType,
+    pub tgid: u32,
+    pub tgdata: T::GroupData,
     pub tests: Vec<AcvpTest<T>>,
-    testgroup_json: JsonValue,
+    pub(crate) testgroup_json: JsonValue,
+    /// Group level result fields loaded from a response file.
+    pub(crate) stored_result: Option<JsonValue>,
 }
 
 impl<T: TestCase> TestGroup for AcvpTestGroup<T> {
-    fn new(algorithm: &str, tgjson: &str) -> AcvpResult<Self> {
-        let tg = match json::parse(tgjson) {
-            Ok(tg) => tg,
-            Err(_e) => {
-                return Err(AcvpError {
-                    code: -libc::EINVAL,
-                    message: "Failed to parse testgroup JSON".to_string(),
-                });
-            }
-        };
-        if !tg.has_key("tgId") || !tg.has_key("testType") {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "Provided testgroup JSON does not have required fields".to_string(),
-            });
-        }
-        let tgid = crate::util::get_acvp_u32("tgId", &tg)?;
-        let test_type = TestType::from_string(&crate::util::get_acvp_str("testType", &tg)?)?;
-
-        let mut direction = Direction::Nil;
-        if tg.has_key("direction") {
-            direction = Direction::from_string(&crate::util::get_acvp_str("direction", &tg)?)?;
-        }
-
-        let mut taglen = 0;
-        if tg.has_key("tagLen") {
-            let taglen_bits = crate::util::get_acvp_u32("tagLen", &tg)? as usize;
-            taglen = taglen_bits / 8;
-        } else if tg.has_key("macLen") {
-            let taglen_bits = crate::util::get_acvp_u32("macLen", &tg)? as usize;
-            taglen = taglen_bits / 8;
-        }
-
-        let mut payload_len = 0;
-        if tg.has_key("payloadLen") {
-            let payloadlen_bits = crate::util::get_acvp_u32("payloadLen", &tg)? as usize;
-            payload_len = payloadlen_bits / 8;
-        }
-
-        let mut ivmode = IVMode::Nil;
-        if tg.has_key("ivGen") {
-            let ivmode_str = crate::util::get_acvp_str("ivGen", &tg)?;
-            ivmode = IVMode::from_string(&ivmode_str)?;
-        }
-
-        let mut ivlen = 0;
-        if tg.has_key("ivLen") {
-            let ivlen_bits = crate::util::get_acvp_u32("ivLen", &tg)? as usize;
-            ivlen = ivlen_bits / 8;
-        }
-
-        let mut drbgmode = DrbgMode::Nil;
-        if tg.has_key("mode") {
-            let mode = crate::util::get_acvp_str("mode", &tg)?;
-            drbgmode = DrbgMode::from_str(&mode)?;
-        }
+    fn new(algorithm: AcvpAlgorithm, tg: &JsonValue) -> AcvpResult<Self> {
+        Self::from_json(algorithm, tg.clone())
+    }
 
-        let mut prediction_resistance = false;
-        if tg.has_key("predResistance") {
-            prediction_resistance = crate::util::get_acvp_bool("predResistance", &tg)?;
-        }
+    fn dump(&self) -> String {
+        self.to_json().dump()
+    }
 
-        let mut der_func = false;
-        if tg.has_key("derFunc") {
-            der_func = crate::util::get_acvp_bool("derFunc", &tg)?;
-        }
+    fn pretty(&self) -> String {
+        self.to_json().pretty(3)
+    }
+}
 
-        let mut reseed = false;
-        if tg.has_key("reSeed") {
-            reseed = crate::util::get_acvp_bool("reSeed", &tg)?;
+impl<T: TestCase> AcvpTestGroup<T> {
+    /// Construct the test group from its JSON object. The test cases are
+    /// moved out of it, the group keeps only its own fields.
+    pub(crate) fn from_json(algorithm: AcvpAlgorithm, mut tg: JsonValue) -> AcvpResult<Self> {
+        if !tg.is_object() {
+            return Err(AcvpError::parse("Testgroup must be a JSON object"));
         }
+        let tgid = crate::util::get_acvp_u32("tgId", &tg)?;
+        let test_type = TestType::from_string(&crate::util::get_acvp_str("testType", &tg)?)
+            .map_err(|e| e.at("testType").with_tgid(tgid))?;
 
-        let mut returned_bits_len: usize = 0;
-        if tg.has_key("returnedBitsLen") {
-            let returned_bits = crate::util::get_acvp_u32("returnedBitsLen", &tg)? as usize;
-            returned_bits_len = returned_bits / 8;
-        }
+        let tgdata = T::GroupData::new(algorithm, test_type, &tg).map_err(|e| e.with_tgid(tgid))?;
 
-        let tgdata = TestGroupData {
-            algorithm: algorithm.to_string(),
-            test_type,
-            taglen,
-            payload_len,
-            ivmode,
-            ivlen,
-            direction,
-            drbgmode,
-            prediction_resistance,
-            der_func,
-            reseed,
-            returned_bits_len,
+        let tcs = match tg.has_key("tests") {
+            true => tg["tests"].take(),
+            false => JsonValue::Null,
+        };
+        let tcs = match tcs {
+            JsonValue::Array(tcs) => tcs,
+            _ => Vec::new(),
         };
-
-        let tcs = &tg["tests"];
         let mut tests = Vec::new();
-        for tc in tcs.members() {
-            let test = AcvpTest::<T>::new(&tc.dump(), &tgdata)?;
+        for (i, tc) in tcs.into_iter().enumerate() {
+            let test = AcvpTest::<T>::from_json(tc, &tgdata)
+                .map_err(|e| e.at(&format!("tests[{}]", i)).with_tgid(tgid))?;
             tests.push(test)
         }
 
         Ok(AcvpTestGroup {
             test_type,
             tgid,
+            tgdata,
             tests,
             testgroup_json: tg,
+            stored_result: None,
         })
     }
 
-    fn dump(&self) -> String {
-        self.testgroup_json.dump()
-    }
-
-    fn pretty(&self) -> String {
-        self.testgroup_json.pretty(3)
+    /// The JSON object of the test group, including its test cases.
+    pub(crate) fn to_json(&self) -> JsonValue {
+        let mut tg = self.testgroup_json.clone();
+        if tg.has_key("tests") {
+            tg["tests"] =
+                JsonValue::Array(self.tests.iter().map(|t| t.test_json.clone()).collect());
+        }
+        tg
     }
 }
 
 impl<T: TestCase> AcvpTestGroup<T> {
     pub fn get_result(&self) -> AcvpResult<JsonValue> {
         let mut results = JsonValue::new_array();
-        for test in &self.tests {
-            let res = test.get_result()?;
+        let mut grp_res = None;
+        for (i, test) in self.tests.iter().enumerate() {
+            let res = test
+                .get_result()
+                .map_err(|e| e.at(&format!("tests[{}]", i)).with_tgid(self.tgid))?;
+            // Group level fields are reported once, every test of the group
+            // must have produced the same ones.
+            match (&grp_res, test.get_group_result()) {
+                (None, res) => grp_res = res,
+                (Some(grp), Some(res)) if *grp != res => {
+                    return Err(AcvpError::invalid_result(
+                        "Group result differs from the one of an earlier test",
+                    )
+                    .at(&format!("tests[{}]", i))
+                    .with_tcid(test.tcid)
+                    .with_tgid(self.tgid));
+                }
+                _ => {}
+            }
             match results.push(res) {
                 Ok(()) => {}
                 Err(_e) => {
-                    return Err(AcvpError {
-                        code: -1,
-                        message: "Unexpected error pushing to JsonValue array".to_string(),
-                    });
+                    return Err(AcvpError::with_code(
+                        -1,
+                        "Unexpected error pushing to JsonValue array",
+                    ));
                 }
             }
         }
-        Ok(json::object! {
+        let mut res = json::object! {
             tgId: self.tgid,
-            tests: results,
-        })
+        };
+        if let Some(grp_res) = grp_res.as_ref().or(self.stored_result.as_ref()) {
+            for (key, val) in grp_res.entries() {
+                res[key] = val.clone();
+            }
+        }
+        res["tests"] = results;
+        Ok(res)
     }
 
     pub fn dump_result(&self) -> AcvpResult<String> {
@@ -313,57 +373,69 @@ impl<T: TestCase> AcvpTestGroup<T> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct AcvpRequest<T> {
+pub struct AcvpRequest<T: TestCase> {
     pub version: String,
     pub revision: String,
     pub vsid: u32,
     pub algorithm: String,
+    pub mode: String,
     pub alg_type: AcvpAlgorithm,
     pub is_sample: bool,
     pub testgroups: Vec<AcvpTestGroup<T>>,
-    request_json: JsonValue,
+    pub(crate) request_json: JsonValue,
 }
 
 impl<T: TestCase> AcvpRequest<T> {
     pub fn new(vector: &str) -> AcvpResult<Self> {
         let request = match json::parse(vector) {
             Ok(req) => req,
-            Err(_e) => {
-                return Err(AcvpError {
-                    code: -libc::EINVAL,
-                    message: "Invalid ACVP Request JSON".to_string(),
-                });
+            Err(e) => {
+                return Err(AcvpError::parse_json("Invalid ACVP Request JSON", e));
             }
         };
+        Self::from_json(request)
+    }
 
+    /// Construct the request from an already parsed vector set. The test
+    /// groups and test cases are built directly from the parsed tree.
+    pub fn from_json(mut request: JsonValue) -> AcvpResult<Self> {
         if !request.is_array() {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "ACVP Request vector must be a JSON Array".to_string(),
-            });
+            return Err(AcvpError::parse("ACVP Request vector must be a JSON Array"));
         }
 
         let mut testgroups = Vec::new();
         let mut algorithm = "".to_string();
+        let mut mode = "".to_string();
         let mut alg_type = AcvpAlgorithm::Nil;
         let mut revision = "".to_string();
         let mut vsid = 0;
         let mut is_sample = false;
         let mut version = "".to_string();
-        for req in request.members() {
+        for (i, req) in request.members_mut().enumerate() {
+            let at = |e: AcvpError| e.at(&format!("$[{}]", i));
             if req.has_key("acvVersion") {
-                version = crate::util::get_acvp_str("acvVersion", req)?;
+                version = crate::util::get_acvp_str("acvVersion", req).map_err(at)?;
                 continue;
             }
-            algorithm = crate::util::get_acvp_str("algorithm", req)?;
-            alg_type = AcvpAlgorithm::alg_type(&algorithm)?;
-            revision = crate::util::get_acvp_str("revision", req)?;
-            vsid = crate::util::get_acvp_u32("vsId", req)?;
-            is_sample = crate::util::get_acvp_bool("isSample", req)?;
-            let tgs = &req["testGroups"];
-
-            for tg in tgs.members() {
-                let testgroup = AcvpTestGroup::<T>::new(&algorithm, &tg.dump())?;
+            vsid = crate::util::get_acvp_u32("vsId", req).map_err(at)?;
+            let at = |e: AcvpError| at(e).with_vsid(vsid);
+            algorithm = crate::util::get_acvp_str("algorithm", req).map_err(at)?;
+            if req.has_key("mode") {
+                mode = crate::util::get_acvp_str("mode", req).map_err(at)?;
+            }
+            revision = crate::util::get_acvp_str("revision", req).map_err(at)?;
+            alg_type =
+                AcvpAlgorithm::alg_type_revision(&algorithm, &mode, &revision).map_err(at)?;
+            is_sample = crate::util::get_acvp_bool("isSample", req).map_err(at)?;
+
+            // The test groups are moved out of the tree, leaving a null in
+            // place of each so that `to_json` can put them back.
+            if !req.has_key("testGroups") {
+                continue;
+            }
+            for (j, tg) in req["testGroups"].members_mut().enumerate() {
+                let testgroup = AcvpTestGroup::<T>::from_json(alg_type, tg.take())
+                    .map_err(|e| at(e.at(&format!("testGroups[{}]", j))))?;
                 testgroups.push(testgroup);
             }
         }
@@ -372,6 +444,7 @@ impl<T: TestCase> AcvpRequest<T> {
             revision,
             vsid,
             algorithm,
+            mode,
             alg_type,
             is_sample,
             testgroups,
@@ -381,28 +454,34 @@ impl<T: TestCase> AcvpRequest<T> {
 
     pub fn get_result(&self) -> AcvpResult<JsonValue> {
         let mut results = JsonValue::new_array();
-        for tg in &self.testgroups {
-            let res = tg.get_result()?;
+        for (j, tg) in self.testgroups.iter().enumerate() {
+            let res = tg.get_result().map_err(|e| {
+                e.at(&format!("$[1].testGroups[{}]", j))
+                    .with_vsid(self.vsid)
+            })?;
             match results.push(res) {
                 Ok(()) => {}
                 Err(_e) => {
-                    return Err(AcvpError {
-                        code: -1,
-                        message: "Unexpected error pushing to JsonValue array".to_string(),
-                    });
+                    return Err(AcvpError::with_code(
+                        -1,
+                        "Unexpected error pushing to JsonValue array",
+                    ));
                 }
             }
         }
         let vers = json::object! {
             acvVersion: self.version.clone()
         };
-        let resp = json::object! {
+        let mut resp = json::object! {
             vsId: self.vsid,
             algorithm: self.algorithm.clone(),
-            revision: self.revision.clone(),
-            isSample: self.is_sample,
-            testGroups: results,
         };
+        if !self.mode.is_empty() {
+            resp["mode"] = self.mode.clone().into();
+        }
+        resp["revision"] = self.revision.clone().into();
+        resp["isSample"] = self.is_sample.into();
+        resp["testGroups"] = results;
         Ok(json::array![vers, resp])
     }
 
@@ -416,11 +495,28 @@ impl<T: TestCase> AcvpRequest<T> {
         Ok(res.pretty(3))
     }
 
+    /// The JSON of the vector set, including its test groups.
+    pub(crate) fn to_json(&self) -> JsonValue {
+        let mut request = self.request_json.clone();
+        let mut testgroups = self.testgroups.iter();
+        for req in request
+            .members_mut()
+            .filter(|req| req.has_key("testGroups"))
+        {
+            for tg in req["testGroups"].members_mut() {
+                if let Some(testgroup) = testgroups.next() {
+                    *tg = testgroup.to_json();
+                }
+            }
+        }
+        request
+    }
+
     pub fn dump(&self) -> String {
-        self.request_json.dump()
+        self.to_json().dump()
     }
 
     pub fn pretty(&self) -> String {
-        self.request_json.pretty(3)
+        self.to_json().pretty(3)
     }
 }
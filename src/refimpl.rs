@@ -0,0 +1,261 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Plain reference implementations of SHA-256, SHA3-256, SHAKE and AES
+//! encryption, to run the Monte Carlo drivers against published known
+//! answers. They favour brevity over speed and are only built for tests.
+
+use std::sync::OnceLock;
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+pub fn sha256(msg: &[u8]) -> Vec<u8> {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    let mut data = msg.to_vec();
+    data.push(0x80);
+    while data.len() % 64 != 56 {
+        data.push(0);
+    }
+    data.extend_from_slice(&(msg.len() as u64 * 8).to_be_bytes());
+
+    for block in data.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let mut v = h;
+        for i in 0..64 {
+            let s1 = v[4].rotate_right(6) ^ v[4].rotate_right(11) ^ v[4].rotate_right(25);
+            let ch = (v[4] & v[5]) ^ (!v[4] & v[6]);
+            let t1 = v[7]
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(SHA256_K[i])
+                .wrapping_add(w[i]);
+            let s0 = v[0].rotate_right(2) ^ v[0].rotate_right(13) ^ v[0].rotate_right(22);
+            let maj = (v[0] & v[1]) ^ (v[0] & v[2]) ^ (v[1] & v[2]);
+            let t2 = s0.wrapping_add(maj);
+            v.copy_within(0..7, 1);
+            v[4] = v[4].wrapping_add(t1);
+            v[0] = t1.wrapping_add(t2);
+        }
+        for (h, v) in h.iter_mut().zip(v) {
+            *h = h.wrapping_add(v);
+        }
+    }
+    h.iter().flat_map(|h| h.to_be_bytes()).collect()
+}
+
+const KECCAK_RC: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+const KECCAK_ROT: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+const KECCAK_PI: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+fn keccak_f(st: &mut [u64; 25]) {
+    for rc in KECCAK_RC {
+        let mut c = [0u64; 5];
+        for (x, c) in c.iter_mut().enumerate() {
+            *c = st[x] ^ st[x + 5] ^ st[x + 10] ^ st[x + 15] ^ st[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                st[5 * y + x] ^= d;
+            }
+        }
+        let mut last = st[1];
+        for (&pi, &rot) in KECCAK_PI.iter().zip(&KECCAK_ROT) {
+            let next = st[pi];
+            st[pi] = last.rotate_left(rot);
+            last = next;
+        }
+        for y in 0..5 {
+            let row = [
+                st[5 * y],
+                st[5 * y + 1],
+                st[5 * y + 2],
+                st[5 * y + 3],
+                st[5 * y + 4],
+            ];
+            for x in 0..5 {
+                st[5 * y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+        st[0] ^= rc;
+    }
+}
+
+/// Keccak sponge with a rate of `rate` bytes and the domain separation
+/// bits `ds`, squeezing `out_len` bytes.
+fn keccak(rate: usize, ds: u8, msg: &[u8], out_len: usize) -> Vec<u8> {
+    let mut st = [0u64; 25];
+    let mut data = msg.to_vec();
+    data.push(ds);
+    while data.len() % rate != 0 {
+        data.push(0);
+    }
+    *data.last_mut().unwrap() |= 0x80;
+
+    for block in data.chunks(rate) {
+        for (lane, bytes) in st.iter_mut().zip(block.chunks(8)) {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(bytes);
+            *lane ^= u64::from_le_bytes(buf);
+        }
+        keccak_f(&mut st);
+    }
+
+    let mut out = Vec::with_capacity(out_len);
+    loop {
+        let block: Vec<u8> = st.iter().flat_map(|l| l.to_le_bytes()).take(rate).collect();
+        let n = (out_len - out.len()).min(rate);
+        out.extend_from_slice(&block[..n]);
+        if out.len() == out_len {
+            return out;
+        }
+        keccak_f(&mut st);
+    }
+}
+
+pub fn sha3_256(msg: &[u8]) -> Vec<u8> {
+    keccak(136, 0x06, msg, 32)
+}
+
+pub fn shake128(msg: &[u8], out_len: usize) -> Vec<u8> {
+    keccak(168, 0x1f, msg, out_len)
+}
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut p = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            p ^= a;
+        }
+        a = (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 };
+        b >>= 1;
+    }
+    p
+}
+
+/// The AES S-box entry of `b`: its multiplicative inverse followed by the
+/// affine transformation.
+fn sbox(b: u8) -> u8 {
+    // b^254 is the inverse of b, and 0 for 0.
+    let mut inv = 1;
+    for _ in 0..254 {
+        inv = gf_mul(inv, b);
+    }
+    inv ^ inv.rotate_left(1) ^ inv.rotate_left(2) ^ inv.rotate_left(3) ^ inv.rotate_left(4) ^ 0x63
+}
+
+/// Encrypt a single block with AES-128, AES-192 or AES-256.
+pub fn aes_encrypt(key: &[u8], block: &[u8]) -> Vec<u8> {
+    static SBOX: OnceLock<Vec<u8>> = OnceLock::new();
+    let sbox = SBOX.get_or_init(|| (0..=255).map(sbox).collect());
+    let nk = key.len() / 4;
+    let rounds = nk + 6;
+
+    let mut w: Vec<[u8; 4]> = key.chunks(4).map(|c| [c[0], c[1], c[2], c[3]]).collect();
+    let mut rcon = 1u8;
+    for i in nk..4 * (rounds + 1) {
+        let mut t = w[i - 1];
+        if i % nk == 0 {
+            t = [
+                sbox[t[1] as usize] ^ rcon,
+                sbox[t[2] as usize],
+                sbox[t[3] as usize],
+                sbox[t[0] as usize],
+            ];
+            rcon = gf_mul(rcon, 2);
+        } else if nk > 6 && i % nk == 4 {
+            t = t.map(|b| sbox[b as usize]);
+        }
+        let prev = w[i - nk];
+        w.push([
+            prev[0] ^ t[0],
+            prev[1] ^ t[1],
+            prev[2] ^ t[2],
+            prev[3] ^ t[3],
+        ]);
+    }
+
+    let mut s = block.to_vec();
+    let add_round_key = |s: &mut Vec<u8>, round: usize| {
+        for (i, b) in s.iter_mut().enumerate() {
+            *b ^= w[4 * round + i / 4][i % 4];
+        }
+    };
+    add_round_key(&mut s, 0);
+    for round in 1..=rounds {
+        s = (0..16)
+            .map(|i| {
+                // ShiftRows: row r of column c comes from column c + r.
+                let (c, r) = (i / 4, i % 4);
+                sbox[s[4 * ((c + r) % 4) + r] as usize]
+            })
+            .collect();
+        if round != rounds {
+            for col in s.chunks_mut(4) {
+                let a = [col[0], col[1], col[2], col[3]];
+                for r in 0..4 {
+                    col[r] = gf_mul(a[r], 2)
+                        ^ gf_mul(a[(r + 1) % 4], 3)
+                        ^ a[(r + 2) % 4]
+                        ^ a[(r + 3) % 4];
+                }
+            }
+        }
+        add_round_key(&mut s, round);
+    }
+    s
+}
@@ -0,0 +1,92 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::sync::RwLock;
+
+use json::JsonValue;
+
+use crate::{
+    dispatch::DynRequest,
+    parser::{AcvpRequest, TestCase},
+    util::AcvpAlgorithm,
+    AcvpResult,
+};
+
+/// Parses a vector set of a custom algorithm into a type erased request.
+pub type CustomLoader = fn(JsonValue) -> AcvpResult<Box<dyn DynRequest>>;
+
+/// Vector sets routed to a custom algorithm handler.
+///
+/// `mode` and `revision` narrow the match when set, a `None` matches any
+/// value of the vector set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomAlgorithm {
+    pub algorithm: String,
+    pub mode: Option<String>,
+    pub revision: Option<String>,
+}
+
+impl CustomAlgorithm {
+    fn matches(&self, alg: &str, mode: &str, revision: &str) -> bool {
+        self.algorithm == alg
+            && self.mode.as_ref().is_none_or(|m| m == mode)
+            && self.revision.as_ref().is_none_or(|r| r == revision)
+    }
+}
+
+struct Entry {
+    alg: CustomAlgorithm,
+    loader: CustomLoader,
+}
+
+static REGISTRY: RwLock<Vec<Entry>> = RwLock::new(Vec::new());
+
+fn load<T: TestCase + 'static>(request: JsonValue) -> AcvpResult<Box<dyn DynRequest>> {
+    Ok(Box::new(AcvpRequest::<T>::from_json(request)?))
+}
+
+/// Register `T` as the handler of the vector sets matching `alg` and return
+/// the `AcvpAlgorithm` those vector sets are classified as.
+///
+/// Registered algorithms take precedence over the built-in ones, registering
+/// the same algorithm again replaces its handler.
+pub fn register<T: TestCase + 'static>(alg: CustomAlgorithm) -> AcvpAlgorithm {
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    let id = match registry.iter().position(|e| e.alg == alg) {
+        Some(id) => {
+            registry[id].loader = load::<T>;
+            id
+        }
+        None => {
+            registry.push(Entry {
+                alg,
+                loader: load::<T>,
+            });
+            registry.len() - 1
+        }
+    };
+    AcvpAlgorithm::Custom(id as u32)
+}
+
+/// Find the custom algorithm registered for a vector set, if any. The last
+/// registered match wins.
+pub fn lookup(alg: &str, mode: &str, revision: &str) -> Option<AcvpAlgorithm> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry
+        .iter()
+        .rposition(|e| e.alg.matches(alg, mode, revision))
+        .map(|id| AcvpAlgorithm::Custom(id as u32))
+}
+
+/// Registration details of a custom algorithm.
+pub fn custom_algorithm(id: u32) -> Option<CustomAlgorithm> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry.get(id as usize).map(|e| e.alg.clone())
+}
+
+pub(crate) fn loader(id: u32) -> Option<CustomLoader> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry.get(id as usize).map(|e| e.loader)
+}
@@ -0,0 +1,189 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use json::JsonValue;
+
+use crate::{
+    parser::{AcvpRequest, TestCase},
+    AcvpError, AcvpResult,
+};
+
+/// Result of a single test case in a response file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseTest {
+    pub tcid: u32,
+    pub result: JsonValue,
+}
+
+/// Results of a test group in a response file. `group_result` holds the
+/// fields reported once per group, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseGroup {
+    pub tgid: u32,
+    pub group_result: Option<JsonValue>,
+    pub tests: Vec<ResponseTest>,
+}
+
+impl ResponseGroup {
+    pub fn find_test(&self, tcid: u32) -> Option<&ResponseTest> {
+        self.tests.iter().find(|t| t.tcid == tcid)
+    }
+}
+
+/// Response to a vector set, as written by `AcvpRequest::dump_result`.
+///
+/// Expected results files of sample vector sets share the same layout and
+/// are parsed the same way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcvpResponse {
+    pub version: String,
+    pub vsid: u32,
+    pub algorithm: String,
+    pub revision: String,
+    pub is_sample: bool,
+    pub testgroups: Vec<ResponseGroup>,
+}
+
+impl AcvpResponse {
+    pub fn new(response: &str) -> AcvpResult<Self> {
+        let resp = match json::parse(response) {
+            Ok(resp) => resp,
+            Err(e) => {
+                return Err(AcvpError::parse_json("Invalid ACVP Response JSON", e));
+            }
+        };
+        Self::from_json(&resp)
+    }
+
+    pub fn from_json(response: &JsonValue) -> AcvpResult<Self> {
+        if !response.is_array() {
+            return Err(AcvpError::parse("ACVP Response must be a JSON Array"));
+        }
+
+        let mut version = "".to_string();
+        let mut vs = None;
+        for (i, member) in response.members().enumerate() {
+            let at = |e: AcvpError| e.at(&format!("$[{}]", i));
+            if member.has_key("acvVersion") {
+                version = crate::util::get_acvp_str("acvVersion", member).map_err(at)?;
+            } else if member.has_key("testGroups") {
+                vs = Some((i, member));
+            }
+        }
+        let (i, vs) = match vs {
+            Some(vs) => vs,
+            None => return Err(AcvpError::missing_field("testGroups", "array")),
+        };
+
+        let at = |e: AcvpError| e.at(&format!("$[{}]", i));
+        let vsid = crate::util::get_acvp_u32("vsId", vs).map_err(at)?;
+        let at = |e: AcvpError| at(e).with_vsid(vsid);
+        let mut algorithm = "".to_string();
+        if vs.has_key("algorithm") {
+            algorithm = crate::util::get_acvp_str("algorithm", vs).map_err(at)?;
+        }
+        let mut revision = "".to_string();
+        if vs.has_key("revision") {
+            revision = crate::util::get_acvp_str("revision", vs).map_err(at)?;
+        }
+        let mut is_sample = false;
+        if vs.has_key("isSample") {
+            is_sample = crate::util::get_acvp_bool("isSample", vs).map_err(at)?;
+        }
+
+        let mut testgroups = Vec::new();
+        for (j, tg) in vs["testGroups"].members().enumerate() {
+            let group =
+                Self::parse_group(tg).map_err(|e| at(e.at(&format!("testGroups[{}]", j))))?;
+            testgroups.push(group);
+        }
+
+        Ok(AcvpResponse {
+            version,
+            vsid,
+            algorithm,
+            revision,
+            is_sample,
+            testgroups,
+        })
+    }
+
+    fn parse_group(tg: &JsonValue) -> AcvpResult<ResponseGroup> {
+        let tgid = crate::util::get_acvp_u32("tgId", tg)?;
+
+        let mut group_result = JsonValue::new_object();
+        for (key, val) in tg.entries() {
+            if key != "tgId" && key != "tests" {
+                group_result[key] = val.clone();
+            }
+        }
+
+        let mut tests = Vec::new();
+        for (i, tc) in tg["tests"].members().enumerate() {
+            let tcid = crate::util::get_acvp_u32("tcId", tc)
+                .map_err(|e| e.at(&format!("tests[{}]", i)).with_tgid(tgid))?;
+            tests.push(ResponseTest {
+                tcid,
+                result: tc.clone(),
+            });
+        }
+
+        Ok(ResponseGroup {
+            tgid,
+            group_result: if group_result.is_empty() {
+                None
+            } else {
+                Some(group_result)
+            },
+            tests,
+        })
+    }
+
+    pub fn find_group(&self, tgid: u32) -> Option<&ResponseGroup> {
+        self.testgroups.iter().find(|g| g.tgid == tgid)
+    }
+}
+
+impl<T: TestCase> AcvpRequest<T> {
+    /// Attach the results of a previously generated response to the
+    /// matching tests. The attached results are reported by `get_result`
+    /// until a result is set on the test. Fails if the response is for
+    /// another vector set or refers to a test group or case absent from the
+    /// request.
+    pub fn attach_response(&mut self, response: &AcvpResponse) -> AcvpResult<()> {
+        if response.vsid != self.vsid {
+            return Err(AcvpError::invalid(format!(
+                "Response is for vsId {}, the request is vsId {}",
+                response.vsid, self.vsid
+            ))
+            .with_vsid(self.vsid));
+        }
+
+        for rgroup in &response.testgroups {
+            let tg = match self.testgroups.iter_mut().find(|g| g.tgid == rgroup.tgid) {
+                Some(tg) => tg,
+                None => {
+                    return Err(AcvpError::invalid("Test group is not part of the request")
+                        .with_vsid(self.vsid)
+                        .with_tgid(rgroup.tgid));
+                }
+            };
+            tg.stored_result = rgroup.group_result.clone();
+            for rtest in &rgroup.tests {
+                let test = match tg.tests.iter_mut().find(|t| t.tcid == rtest.tcid) {
+                    Some(test) => test,
+                    None => {
+                        return Err(AcvpError::invalid("Test case is not part of the request")
+                            .with_vsid(self.vsid)
+                            .with_tgid(rgroup.tgid)
+                            .with_tcid(rtest.tcid));
+                    }
+                };
+                test.stored_result = Some(rtest.result.clone());
+            }
+        }
+        Ok(())
+    }
+}
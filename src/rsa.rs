@@ -0,0 +1,201 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use json::JsonValue;
+
+use crate::{
+    backend::{Backend, BackendTest},
+    dispatch::AnyResult,
+    ifc::{IfcKey, IfcKeyFormat},
+    parser::{GroupData, TestCase, TestResult},
+    util::{AcvpAlgorithm, TestType},
+    AcvpError, AcvpResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RsaPrimitiveMode {
+    DecryptionPrimitive,
+    SignaturePrimitive,
+}
+
+impl std::str::FromStr for RsaPrimitiveMode {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "decryptionPrimitive" => Ok(Self::DecryptionPrimitive),
+            "signaturePrimitive" => Ok(Self::SignaturePrimitive),
+            _ => Err(AcvpError::unsupported(format!(
+                "RSA primitive mode '{}' is not valid",
+                s
+            ))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RsaPrimitiveGroupData {
+    pub algorithm: AcvpAlgorithm,
+    pub test_type: TestType,
+    pub mode: RsaPrimitiveMode,
+    pub modulo: usize,
+    pub key_format: IfcKeyFormat,
+}
+
+impl GroupData for RsaPrimitiveGroupData {
+    fn new(algorithm: AcvpAlgorithm, test_type: TestType, tg: &JsonValue) -> AcvpResult<Self> {
+        let mode = match algorithm {
+            AcvpAlgorithm::Rsa(mode) => mode,
+            _ => {
+                return Err(AcvpError::invalid("Algorithm is not RSA"));
+            }
+        };
+
+        let mut modulo = 0;
+        if tg.has_key("modulo") {
+            modulo = crate::util::get_acvp_u32("modulo", tg)? as usize;
+        }
+
+        let mut key_format = IfcKeyFormat::Standard;
+        if tg.has_key("keyFormat") {
+            key_format = crate::util::get_acvp_str("keyFormat", tg)?.parse()?;
+        }
+
+        Ok(RsaPrimitiveGroupData {
+            algorithm,
+            test_type,
+            mode,
+            modulo,
+            key_format,
+        })
+    }
+}
+
+/// RSA decryption primitive (RSADP) and signature primitive (RSASP1)
+/// component test case.
+///
+/// `input` holds the ciphertext for the decryption primitive and the message
+/// representative for the signature primitive. A successful operation sets
+/// its output, the plaintext or the signature. When the input is out of range
+/// for the key, the IUT is expected to fail the operation and report it by
+/// setting a `false` result.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RsaPrimitive {
+    algorithm: AcvpAlgorithm,
+    tcid: u32,
+    test_type: TestType,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::json_value"))]
+    res_json: JsonValue,
+    pub mode: RsaPrimitiveMode,
+    pub modulo: usize,
+    pub key_format: IfcKeyFormat,
+    pub key: IfcKey,
+    pub input: Vec<u8>,
+}
+
+impl TestCase for RsaPrimitive {
+    type GroupData = RsaPrimitiveGroupData;
+
+    fn new(test: &JsonValue, tgdata: &RsaPrimitiveGroupData) -> AcvpResult<Self> {
+        let tcid = crate::util::get_acvp_u32("tcId", test)?;
+        let mode = tgdata.mode;
+
+        let key = IfcKey::new("", test)?;
+
+        let input = match mode {
+            RsaPrimitiveMode::DecryptionPrimitive if test.has_key("cipherText") => {
+                crate::util::get_acvp_mpi("cipherText", test)?
+            }
+            RsaPrimitiveMode::DecryptionPrimitive => crate::util::get_acvp_mpi("c", test)?,
+            RsaPrimitiveMode::SignaturePrimitive => crate::util::get_acvp_mpi("message", test)?,
+        };
+
+        Ok(RsaPrimitive {
+            algorithm: tgdata.algorithm,
+            tcid,
+            test_type: tgdata.test_type,
+            res_json: JsonValue::new_object(),
+            mode,
+            modulo: tgdata.modulo,
+            key_format: tgdata.key_format,
+            key,
+            input,
+        })
+    }
+
+    fn get_result(&self) -> AcvpResult<JsonValue> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.clone())
+    }
+
+    fn dump_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.dump())
+    }
+
+    fn pretty_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.pretty(3))
+    }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        match res {
+            AnyResult::Bool(res) => self.set_result(res),
+            AnyResult::Bytes(res) => self.set_result(res),
+            res => Err(res.unsupported()),
+        }
+    }
+}
+
+impl TestResult<Vec<u8>> for RsaPrimitive {
+    fn set_result(&mut self, result: Vec<u8>) -> AcvpResult<()> {
+        let mut res = JsonValue::new_object();
+        res["tcId"] = self.tcid.into();
+        res["testPassed"] = true.into();
+        match self.mode {
+            RsaPrimitiveMode::DecryptionPrimitive => {
+                res["pt"] = hex::encode(result).to_ascii_uppercase().into();
+            }
+            RsaPrimitiveMode::SignaturePrimitive => {
+                res["signature"] = hex::encode(result).to_ascii_uppercase().into();
+            }
+        }
+        self.res_json = res;
+        Ok(())
+    }
+}
+
+impl TestResult<bool> for RsaPrimitive {
+    fn set_result(&mut self, result: bool) -> AcvpResult<()> {
+        if result {
+            return Err(AcvpError::invalid_result(
+                "Successful RSA primitive operations must set the output",
+            ));
+        }
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            testPassed: false,
+        };
+        Ok(())
+    }
+}
+
+impl BackendTest for RsaPrimitive {
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        match backend.rsa_primitive(self)? {
+            Some(out) => self.set_result(out),
+            None => self.set_result(false),
+        }
+    }
+}
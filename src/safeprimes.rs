@@ -0,0 +1,195 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use json::JsonValue;
+
+use crate::{
+    backend::{Backend, BackendTest},
+    dispatch::AnyResult,
+    parser::{GroupData, TestCase, TestResult},
+    util::{AcvpAlgorithm, TestType},
+    AcvpError, AcvpResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SafePrimesMode {
+    KeyGen,
+    KeyVer,
+}
+
+impl std::str::FromStr for SafePrimesMode {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keyGen" => Ok(Self::KeyGen),
+            "keyVer" => Ok(Self::KeyVer),
+            _ => Err(AcvpError::unsupported(format!(
+                "safePrimes mode '{}' is not valid",
+                s
+            ))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+/// Key pair generated by the IUT for a safe primes keyGen test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SafePrimesKeyOutput {
+    pub x: Vec<u8>,
+    pub y: Vec<u8>,
+}
+
+impl SafePrimesKeyOutput {
+    pub fn new(x: Vec<u8>, y: Vec<u8>) -> Self {
+        SafePrimesKeyOutput { x, y }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SafePrimesGroupData {
+    pub algorithm: AcvpAlgorithm,
+    pub test_type: TestType,
+    pub mode: SafePrimesMode,
+    pub safe_prime_group: String,
+}
+
+impl GroupData for SafePrimesGroupData {
+    fn new(algorithm: AcvpAlgorithm, test_type: TestType, tg: &JsonValue) -> AcvpResult<Self> {
+        let mode = match algorithm {
+            AcvpAlgorithm::SafePrimes(mode) => mode,
+            _ => {
+                return Err(AcvpError::invalid("Algorithm is not safePrimes"));
+            }
+        };
+        let safe_prime_group = crate::util::get_acvp_str("safePrimeGroup", tg)?;
+
+        Ok(SafePrimesGroupData {
+            algorithm,
+            test_type,
+            mode,
+            safe_prime_group,
+        })
+    }
+}
+
+/// Safe primes (SP800-56Ar3 ffdhe/MODP groups) keyGen and keyVer test case.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SafePrimes {
+    algorithm: AcvpAlgorithm,
+    tcid: u32,
+    test_type: TestType,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::json_value"))]
+    res_json: JsonValue,
+    pub mode: SafePrimesMode,
+    pub safe_prime_group: String,
+    pub x: Vec<u8>,
+    pub y: Vec<u8>,
+}
+
+impl TestCase for SafePrimes {
+    type GroupData = SafePrimesGroupData;
+
+    fn new(test: &JsonValue, tgdata: &SafePrimesGroupData) -> AcvpResult<Self> {
+        let tcid = crate::util::get_acvp_u32("tcId", test)?;
+        let mode = tgdata.mode;
+
+        let mut x = Vec::new();
+        let mut y = Vec::new();
+        if mode == SafePrimesMode::KeyVer {
+            x = crate::util::get_acvp_mpi("x", test)?;
+            y = crate::util::get_acvp_mpi("y", test)?;
+        }
+
+        Ok(SafePrimes {
+            algorithm: tgdata.algorithm,
+            tcid,
+            test_type: tgdata.test_type,
+            res_json: JsonValue::new_object(),
+            mode,
+            safe_prime_group: tgdata.safe_prime_group.to_string(),
+            x,
+            y,
+        })
+    }
+
+    fn get_result(&self) -> AcvpResult<JsonValue> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.clone())
+    }
+
+    fn dump_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.dump())
+    }
+
+    fn pretty_result(&self) -> AcvpResult<String> {
+        if self.res_json.is_empty() {
+            return Err(AcvpError::result_not_set());
+        }
+        Ok(self.res_json.pretty(3))
+    }
+
+    fn set_any_result(&mut self, res: AnyResult) -> AcvpResult<()> {
+        match res {
+            AnyResult::Bool(res) => self.set_result(res),
+            AnyResult::SafePrimesKey(res) => self.set_result(res),
+            res => Err(res.unsupported()),
+        }
+    }
+}
+
+impl TestResult<SafePrimesKeyOutput> for SafePrimes {
+    fn set_result(&mut self, result: SafePrimesKeyOutput) -> AcvpResult<()> {
+        if self.mode != SafePrimesMode::KeyGen {
+            return Err(AcvpError::invalid_result(
+                "Key pair can only be set for safePrimes keyGen",
+            ));
+        }
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            x: crate::util::mpi2hex(&result.x),
+            y: crate::util::mpi2hex(&result.y),
+        };
+        Ok(())
+    }
+}
+
+impl TestResult<bool> for SafePrimes {
+    fn set_result(&mut self, result: bool) -> AcvpResult<()> {
+        if self.mode != SafePrimesMode::KeyVer {
+            return Err(AcvpError::invalid_result(
+                "Verification result can only be set for safePrimes keyVer",
+            ));
+        }
+        self.res_json = json::object! {
+            tcId: self.tcid,
+            testPassed: result,
+        };
+        Ok(())
+    }
+}
+
+impl BackendTest for SafePrimes {
+    fn run<B: Backend + ?Sized>(&mut self, backend: &B) -> AcvpResult<()> {
+        match self.mode {
+            SafePrimesMode::KeyGen => {
+                let key = backend.safe_primes_key_gen(self)?;
+                self.set_result(key)
+            }
+            SafePrimesMode::KeyVer => {
+                let ok = backend.safe_primes_key_ver(self)?;
+                self.set_result(ok)
+            }
+        }
+    }
+}
@@ -0,0 +1,409 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Serde support, enabled with the `serde` feature.
+//!
+//! Requests, test groups and test cases serialise to the ACVP JSON they were
+//! parsed from, so they can be cached and restored. The result set on a test
+//! is kept as its `result` field and the group level result fields as the
+//! `groupResult` field of the group, deserialising restores them as if
+//! attached from a response. The fields identifying the vector set, groups
+//! and tests are required by the serde models below, the others are checked
+//! by the same typed constructors as `AcvpRequest::new`, so a missing or
+//! malformed field fails deserialisation with the `AcvpError` message. Test
+//! groups and test cases depend on their vector set, they are deserialised
+//! with `TestGroupSeed` and `TestCaseSeed`.
+//!
+//! The typed test cases, e.g. `SecureHash`, derive `Serialize` and
+//! `Deserialize` themselves. Responses and any other `JsonValue` are wrapped
+//! in `Json`.
+
+use std::{fmt, marker::PhantomData};
+
+use json::{object::Object, JsonValue};
+use serde::{
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    parser::{AcvpRequest, AcvpTest, AcvpTestGroup, TestCase},
+    util::AcvpAlgorithm,
+};
+
+/// `JsonValue` which implements `Serialize` and `Deserialize`, e.g. the
+/// response returned by `AcvpRequest::get_result`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json(pub JsonValue);
+
+struct JsonRef<'a>(&'a JsonValue);
+
+impl Serialize for JsonRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            JsonValue::Null => serializer.serialize_unit(),
+            JsonValue::Short(s) => serializer.serialize_str(s.as_str()),
+            JsonValue::String(s) => serializer.serialize_str(s),
+            JsonValue::Boolean(b) => serializer.serialize_bool(*b),
+            JsonValue::Number(_) => {
+                if let Some(u) = self.0.as_u64() {
+                    serializer.serialize_u64(u)
+                } else if let Some(i) = self.0.as_i64() {
+                    serializer.serialize_i64(i)
+                } else {
+                    serializer.serialize_f64(self.0.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            JsonValue::Object(obj) => {
+                let mut map = serializer.serialize_map(Some(obj.len()))?;
+                for (key, val) in obj.iter() {
+                    map.serialize_entry(key, &JsonRef(val))?;
+                }
+                map.end()
+            }
+            JsonValue::Array(arr) => {
+                let mut seq = serializer.serialize_seq(Some(arr.len()))?;
+                for val in arr {
+                    seq.serialize_element(&JsonRef(val))?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+impl Serialize for Json {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        JsonRef(&self.0).serialize(serializer)
+    }
+}
+
+struct JsonVisitor;
+
+impl<'de> Visitor<'de> for JsonVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<JsonValue, E> {
+        Ok(v.into())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<JsonValue, E> {
+        Ok(v.into())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<JsonValue, E> {
+        Ok(v.into())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<JsonValue, E> {
+        Ok(v.into())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<JsonValue, E> {
+        Ok(v.into())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<JsonValue, E> {
+        Ok(v.into())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut arr = Vec::new();
+        while let Some(Json(val)) = seq.next_element()? {
+            arr.push(val);
+        }
+        Ok(JsonValue::Array(arr))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+        let mut obj = Object::new();
+        while let Some((key, Json(val))) = map.next_entry::<String, Json>()? {
+            obj.insert(&key, val);
+        }
+        Ok(JsonValue::Object(obj))
+    }
+}
+
+impl<'de> Deserialize<'de> for Json {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonVisitor).map(Json)
+    }
+}
+
+/// Serde `with` module for `JsonValue` fields.
+pub(crate) mod json_value {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(val: &JsonValue, serializer: S) -> Result<S::Ok, S::Error> {
+        JsonRef(val).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<JsonValue, D::Error> {
+        Json::deserialize(deserializer).map(|Json(val)| val)
+    }
+}
+
+/// Move the entries of `from` into the object `to`.
+fn extend(to: &mut JsonValue, mut from: JsonValue) {
+    for (key, val) in from.entries_mut() {
+        to[key] = val.take();
+    }
+}
+
+#[derive(Deserialize)]
+struct TestCaseJson {
+    #[serde(rename = "tcId")]
+    tcid: u32,
+    #[serde(default)]
+    result: Option<Json>,
+    #[serde(flatten)]
+    fields: Json,
+}
+
+impl TestCaseJson {
+    /// Split into the ACVP JSON of the test case and its result.
+    fn into_parts(self) -> (JsonValue, Option<JsonValue>) {
+        let mut tc = json::object! { tcId: self.tcid };
+        extend(&mut tc, self.fields.0);
+        (tc, self.result.map(|Json(res)| res))
+    }
+}
+
+#[derive(Deserialize)]
+struct TestGroupJson {
+    #[serde(rename = "tgId")]
+    tgid: u32,
+    #[serde(rename = "testType")]
+    test_type: String,
+    #[serde(default)]
+    tests: Option<Vec<TestCaseJson>>,
+    #[serde(rename = "groupResult", default)]
+    group_result: Option<Json>,
+    #[serde(flatten)]
+    fields: Json,
+}
+
+/// Results of a deserialised test group and its tests, in test order.
+struct GroupResults {
+    group: Option<JsonValue>,
+    tests: Vec<Option<JsonValue>>,
+}
+
+impl GroupResults {
+    fn restore<T: TestCase>(self, tg: &mut AcvpTestGroup<T>) {
+        tg.stored_result = self.group;
+        for (test, res) in tg.tests.iter_mut().zip(self.tests) {
+            test.stored_result = res;
+        }
+    }
+}
+
+impl TestGroupJson {
+    /// Split into the ACVP JSON of the test group and its results.
+    fn into_parts(self) -> (JsonValue, GroupResults) {
+        let mut tg = json::object! {
+            tgId: self.tgid,
+            testType: self.test_type,
+        };
+        extend(&mut tg, self.fields.0);
+        let mut results = GroupResults {
+            group: self.group_result.map(|Json(res)| res),
+            tests: Vec::new(),
+        };
+        if let Some(tests) = self.tests {
+            let mut tcs = Vec::new();
+            for test in tests {
+                let (tc, res) = test.into_parts();
+                tcs.push(tc);
+                results.tests.push(res);
+            }
+            tg["tests"] = JsonValue::Array(tcs);
+        }
+        (tg, results)
+    }
+}
+
+#[derive(Deserialize)]
+struct VersionJson {
+    #[serde(rename = "acvVersion")]
+    version: String,
+    #[serde(flatten)]
+    fields: Json,
+}
+
+#[derive(Deserialize)]
+struct VectorSetJson {
+    #[serde(rename = "vsId")]
+    vsid: u32,
+    algorithm: String,
+    revision: String,
+    #[serde(rename = "isSample")]
+    is_sample: bool,
+    #[serde(rename = "testGroups")]
+    testgroups: Vec<TestGroupJson>,
+    #[serde(flatten)]
+    fields: Json,
+}
+
+/// An ACVP request: the version object followed by the vector set.
+#[derive(Deserialize)]
+struct RequestJson(VersionJson, VectorSetJson);
+
+impl<T: TestCase> AcvpTest<T> {
+    /// The JSON object of the test case, with its result, if set.
+    fn to_serde_json(&self) -> JsonValue {
+        let mut tc = self.test_json.clone();
+        if let Ok(res) = self.get_result() {
+            tc["result"] = res;
+        }
+        tc
+    }
+}
+
+impl<T: TestCase> AcvpTestGroup<T> {
+    /// The JSON object of the test group, with the results of the group and
+    /// its tests, if set.
+    fn to_serde_json(&self) -> JsonValue {
+        let mut tg = self.testgroup_json.clone();
+        if tg.has_key("tests") {
+            tg["tests"] = JsonValue::Array(self.tests.iter().map(|t| t.to_serde_json()).collect());
+        }
+        let grp_res = self
+            .tests
+            .iter()
+            .find_map(|t| t.get_group_result())
+            .or_else(|| self.stored_result.clone());
+        if let Some(grp_res) = grp_res {
+            tg["groupResult"] = grp_res;
+        }
+        tg
+    }
+}
+
+impl<T: TestCase> Serialize for AcvpRequest<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut request = self.request_json.clone();
+        let mut testgroups = self.testgroups.iter();
+        for req in request
+            .members_mut()
+            .filter(|req| req.has_key("testGroups"))
+        {
+            for tg in req["testGroups"].members_mut() {
+                if let Some(testgroup) = testgroups.next() {
+                    *tg = testgroup.to_serde_json();
+                }
+            }
+        }
+        JsonRef(&request).serialize(serializer)
+    }
+}
+
+impl<'de, T: TestCase> Deserialize<'de> for AcvpRequest<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let RequestJson(version, vs) = RequestJson::deserialize(deserializer)?;
+
+        let mut vers = json::object! { acvVersion: version.version };
+        extend(&mut vers, version.fields.0);
+        let mut req = json::object! {
+            vsId: vs.vsid,
+            algorithm: vs.algorithm,
+        };
+        extend(&mut req, vs.fields.0);
+        req["revision"] = vs.revision.into();
+        req["isSample"] = vs.is_sample.into();
+        let mut tgs = Vec::new();
+        let mut results = Vec::new();
+        for tg in vs.testgroups {
+            let (tg, res) = tg.into_parts();
+            tgs.push(tg);
+            results.push(res);
+        }
+        req["testGroups"] = JsonValue::Array(tgs);
+
+        let mut request =
+            AcvpRequest::from_json(json::array![vers, req]).map_err(de::Error::custom)?;
+        for (tg, res) in request.testgroups.iter_mut().zip(results) {
+            res.restore(tg);
+        }
+        Ok(request)
+    }
+}
+
+impl<T: TestCase> Serialize for AcvpTestGroup<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        JsonRef(&self.to_serde_json()).serialize(serializer)
+    }
+}
+
+impl<T: TestCase> Serialize for AcvpTest<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        JsonRef(&self.to_serde_json()).serialize(serializer)
+    }
+}
+
+/// Deserialise a test group of a vector set of `algorithm`.
+pub struct TestGroupSeed<T> {
+    algorithm: AcvpAlgorithm,
+    test: PhantomData<T>,
+}
+
+impl<T> TestGroupSeed<T> {
+    pub fn new(algorithm: AcvpAlgorithm) -> Self {
+        TestGroupSeed {
+            algorithm,
+            test: PhantomData,
+        }
+    }
+}
+
+impl<'de, T: TestCase> DeserializeSeed<'de> for TestGroupSeed<T> {
+    type Value = AcvpTestGroup<T>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let (tg, results) = TestGroupJson::deserialize(deserializer)?.into_parts();
+        let mut tg = AcvpTestGroup::from_json(self.algorithm, tg).map_err(de::Error::custom)?;
+        results.restore(&mut tg);
+        Ok(tg)
+    }
+}
+
+/// Deserialise a test case of the test group described by `tgdata`.
+pub struct TestCaseSeed<'a, T: TestCase> {
+    tgdata: &'a T::GroupData,
+}
+
+impl<'a, T: TestCase> TestCaseSeed<'a, T> {
+    pub fn new(tgdata: &'a T::GroupData) -> Self {
+        TestCaseSeed { tgdata }
+    }
+}
+
+impl<'de, T: TestCase> DeserializeSeed<'de> for TestCaseSeed<'_, T> {
+    type Value = AcvpTest<T>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let (tc, res) = TestCaseJson::deserialize(deserializer)?.into_parts();
+        let mut test = AcvpTest::from_json(tc, self.tgdata).map_err(de::Error::custom)?;
+        test.stored_result = res;
+        Ok(test)
+    }
+}
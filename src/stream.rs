@@ -0,0 +1,511 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Incremental processing of vector sets too large to hold in memory.
+//!
+//! `RequestReader` reads a vector set from any `Read` and yields one test
+//! group and one test case at a time, only the test case being parsed is
+//! held in memory. `ResponseWriter` writes the response to any `Write` as
+//! the results become available.
+//!
+//! The vector set header fields must precede `testGroups`, and the test
+//! group fields must precede `tests`, as in the vector sets served by ACVP.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    marker::PhantomData,
+};
+
+use json::JsonValue;
+
+use crate::{
+    parser::{AcvpTest, AcvpTestGroup, TestCase},
+    util::AcvpAlgorithm,
+    AcvpError, AcvpResult,
+};
+
+/// Vector set fields preceding the test groups.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorSetHeader {
+    pub version: String,
+    pub vsid: u32,
+    pub algorithm: String,
+    pub mode: String,
+    pub revision: String,
+    pub is_sample: bool,
+    pub alg_type: AcvpAlgorithm,
+}
+
+struct Scanner<R: Read> {
+    reader: BufReader<R>,
+}
+
+impl<R: Read> Scanner<R> {
+    fn peek(&mut self) -> AcvpResult<Option<u8>> {
+        let buf = self.reader.fill_buf().map_err(AcvpError::io)?;
+        Ok(buf.first().copied())
+    }
+
+    fn skip_ws(&mut self) -> AcvpResult<Option<u8>> {
+        loop {
+            match self.peek()? {
+                Some(b) if b.is_ascii_whitespace() => self.reader.consume(1),
+                other => return Ok(other),
+            }
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> AcvpResult<()> {
+        match self.skip_ws()? {
+            Some(b) if b == c => {
+                self.reader.consume(1);
+                Ok(())
+            }
+            Some(b) => Err(AcvpError::parse(format!(
+                "Expected '{}' but found '{}'",
+                c as char, b as char
+            ))),
+            None => Err(AcvpError::parse(format!(
+                "Expected '{}' but found end of input",
+                c as char
+            ))),
+        }
+    }
+
+    /// Advance to the next member of an array or object. Returns false and
+    /// consumes the closing bracket once there are no more members.
+    fn next_member(&mut self, first: &mut bool, close: u8) -> AcvpResult<bool> {
+        if self.skip_ws()? == Some(close) {
+            self.reader.consume(1);
+            return Ok(false);
+        }
+        if !*first {
+            self.expect(b',')?;
+        }
+        *first = false;
+        Ok(true)
+    }
+
+    /// Read the raw text of the next JSON value.
+    fn read_raw(&mut self) -> AcvpResult<Vec<u8>> {
+        self.skip_ws()?;
+        let mut raw = Vec::new();
+        let mut depth = 0usize;
+        let mut in_str = false;
+        let mut escape = false;
+        loop {
+            let buf = self.reader.fill_buf().map_err(AcvpError::io)?;
+            if buf.is_empty() {
+                if depth == 0 && !in_str && !raw.is_empty() {
+                    break;
+                }
+                return Err(AcvpError::parse("Unexpected end of input"));
+            }
+            let mut n = 0;
+            let mut done = false;
+            for &b in buf {
+                if in_str {
+                    n += 1;
+                    if escape {
+                        escape = false;
+                    } else if b == b'\\' {
+                        escape = true;
+                    } else if b == b'"' {
+                        in_str = false;
+                        if depth == 0 {
+                            done = true;
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                match b {
+                    b'"' => in_str = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        if depth == 0 {
+                            done = true;
+                            break;
+                        }
+                        depth -= 1;
+                        if depth == 0 {
+                            n += 1;
+                            done = true;
+                            break;
+                        }
+                    }
+                    b',' if depth == 0 => {
+                        done = true;
+                        break;
+                    }
+                    _ if depth == 0 && b.is_ascii_whitespace() => {
+                        done = true;
+                        break;
+                    }
+                    _ => {}
+                }
+                n += 1;
+            }
+            raw.extend_from_slice(&buf[..n]);
+            self.reader.consume(n);
+            if done {
+                break;
+            }
+        }
+        if raw.is_empty() {
+            return Err(AcvpError::parse("Expected a JSON value"));
+        }
+        Ok(raw)
+    }
+
+    fn read_value(&mut self) -> AcvpResult<JsonValue> {
+        let raw = self.read_raw()?;
+        let text = match std::str::from_utf8(&raw) {
+            Ok(text) => text,
+            Err(_e) => return Err(AcvpError::parse("Invalid UTF-8 in input vector")),
+        };
+        match json::parse(text) {
+            Ok(val) => Ok(val),
+            Err(e) => Err(AcvpError::parse_json("Invalid ACVP Request JSON", e)),
+        }
+    }
+
+    fn read_key(&mut self) -> AcvpResult<String> {
+        let key = match self.read_value()? {
+            JsonValue::Short(key) => key.to_string(),
+            JsonValue::String(key) => key,
+            _ => return Err(AcvpError::parse("Object keys must be strings")),
+        };
+        self.expect(b':')?;
+        Ok(key)
+    }
+
+    /// Skip the remaining members of the current object.
+    fn skip_object(&mut self, first: &mut bool) -> AcvpResult<()> {
+        while self.next_member(first, b'}')? {
+            self.read_key()?;
+            self.read_raw()?;
+        }
+        Ok(())
+    }
+}
+
+enum State {
+    Groups { first: bool },
+    Tests { first: bool },
+    Done,
+}
+
+/// Reads a vector set incrementally, see the module documentation.
+pub struct RequestReader<T: TestCase, R: Read> {
+    pub header: VectorSetHeader,
+    scanner: Scanner<R>,
+    state: State,
+    vs_index: usize,
+    tg_index: usize,
+    tc_index: usize,
+    tgid: u32,
+    tgdata: Option<T::GroupData>,
+    test: PhantomData<T>,
+}
+
+impl<T: TestCase, R: Read> RequestReader<T, R> {
+    /// Read the vector set header, up to the first test group.
+    pub fn new(reader: R) -> AcvpResult<Self> {
+        let mut scanner = Scanner {
+            reader: BufReader::new(reader),
+        };
+        scanner.expect(b'[')?;
+
+        let mut version = "".to_string();
+        let mut vs = JsonValue::new_object();
+        let mut vs_index = 0;
+        let mut first = true;
+        let mut found = false;
+        while !found && scanner.next_member(&mut first, b']')? {
+            scanner.expect(b'{')?;
+            let mut obj = JsonValue::new_object();
+            let mut first_key = true;
+            while scanner.next_member(&mut first_key, b'}')? {
+                let key = scanner.read_key()?;
+                if key == "testGroups" {
+                    found = true;
+                    break;
+                }
+                obj[key.as_str()] = scanner.read_value()?;
+            }
+            if found {
+                vs = obj;
+            } else if obj.has_key("acvVersion") {
+                version = crate::util::get_acvp_str("acvVersion", &obj)
+                    .map_err(|e| e.at(&format!("$[{}]", vs_index)))?;
+            }
+            if !found {
+                vs_index += 1;
+            }
+        }
+        if !found {
+            return Err(AcvpError::missing_field("testGroups", "array"));
+        }
+
+        let at = |e: AcvpError| e.at(&format!("$[{}]", vs_index));
+        let vsid = crate::util::get_acvp_u32("vsId", &vs).map_err(at)?;
+        let at = |e: AcvpError| at(e).with_vsid(vsid);
+        let algorithm = crate::util::get_acvp_str("algorithm", &vs).map_err(at)?;
+        let mut mode = "".to_string();
+        if vs.has_key("mode") {
+            mode = crate::util::get_acvp_str("mode", &vs).map_err(at)?;
+        }
+        let revision = crate::util::get_acvp_str("revision", &vs).map_err(at)?;
+        let alg_type =
+            AcvpAlgorithm::alg_type_revision(&algorithm, &mode, &revision).map_err(at)?;
+        let is_sample = crate::util::get_acvp_bool("isSample", &vs).map_err(at)?;
+        scanner.expect(b'[').map_err(|e| at(e.at("testGroups")))?;
+
+        Ok(RequestReader {
+            header: VectorSetHeader {
+                version,
+                vsid,
+                algorithm,
+                mode,
+                revision,
+                is_sample,
+                alg_type,
+            },
+            scanner,
+            state: State::Groups { first: true },
+            vs_index,
+            tg_index: 0,
+            tc_index: 0,
+            tgid: 0,
+            tgdata: None,
+            test: PhantomData,
+        })
+    }
+
+    fn group_path(&self) -> String {
+        format!("$[{}].testGroups[{}]", self.vs_index, self.tg_index)
+    }
+
+    /// Read the fields of the next test group. The returned group holds no
+    /// tests, they are read with `next_test`. Tests of the current group
+    /// which were not read are skipped.
+    pub fn next_group(&mut self) -> AcvpResult<Option<AcvpTestGroup<T>>> {
+        if let State::Tests { first } = &mut self.state {
+            let mut first = *first;
+            while self.scanner.next_member(&mut first, b']')? {
+                self.scanner.read_raw()?;
+            }
+            let mut first_key = false;
+            self.scanner.skip_object(&mut first_key)?;
+            self.state = State::Groups { first: false };
+        }
+
+        let first = match &mut self.state {
+            State::Groups { first } => first,
+            State::Tests { .. } | State::Done => return Ok(None),
+        };
+        let had_first = *first;
+        if !self.scanner.next_member(first, b']')? {
+            self.state = State::Done;
+            return Ok(None);
+        }
+        if !had_first {
+            self.tg_index += 1;
+        }
+
+        let vsid = self.header.vsid;
+        let path = self.group_path();
+        let at = |e: AcvpError| e.at(&path).with_vsid(vsid);
+        self.scanner.expect(b'{').map_err(at)?;
+        let mut tg = JsonValue::new_object();
+        let mut first_key = true;
+        let mut has_tests = false;
+        while self.scanner.next_member(&mut first_key, b'}').map_err(at)? {
+            let key = self.scanner.read_key().map_err(at)?;
+            if key == "tests" {
+                has_tests = true;
+                break;
+            }
+            tg[key.as_str()] = self.scanner.read_value().map_err(at)?;
+        }
+
+        let group = AcvpTestGroup::<T>::from_json(self.header.alg_type, tg).map_err(at)?;
+        if has_tests {
+            self.scanner.expect(b'[').map_err(|e| at(e.at("tests")))?;
+            self.state = State::Tests { first: true };
+        } else {
+            self.state = State::Groups { first: false };
+        }
+        self.tc_index = 0;
+        self.tgid = group.tgid;
+        self.tgdata = Some(group.tgdata.clone());
+        Ok(Some(group))
+    }
+
+    /// Read the next test case of the current test group, `None` once the
+    /// tests of the group are exhausted.
+    pub fn next_test(&mut self) -> AcvpResult<Option<AcvpTest<T>>> {
+        let vsid = self.header.vsid;
+        let tgid = self.tgid;
+        let group_path = self.group_path();
+        let first = match &mut self.state {
+            State::Tests { first } => first,
+            State::Groups { .. } | State::Done => return Ok(None),
+        };
+        let had_first = *first;
+        let at = |e: AcvpError| e.at(&group_path).with_vsid(vsid).with_tgid(tgid);
+        if !self.scanner.next_member(first, b']').map_err(at)? {
+            let mut first_key = false;
+            self.scanner.skip_object(&mut first_key).map_err(at)?;
+            self.state = State::Groups { first: false };
+            return Ok(None);
+        }
+        if !had_first {
+            self.tc_index += 1;
+        }
+
+        let tc_path = format!("tests[{}]", self.tc_index);
+        let at = |e: AcvpError| at(e.at(&tc_path));
+        let tc = self.scanner.read_value().map_err(at)?;
+        let tgdata = match &self.tgdata {
+            Some(tgdata) => tgdata,
+            None => return Err(AcvpError::invalid("No test group has been read")),
+        };
+        let test = AcvpTest::<T>::from_json(tc, tgdata).map_err(at)?;
+        Ok(Some(test))
+    }
+}
+
+enum WriterState {
+    Groups,
+    Group { tgid: u32, tests: usize },
+    Finished,
+}
+
+/// Writes a response incrementally, see the module documentation.
+///
+/// The output matches `AcvpRequest::dump_result` for the same results.
+pub struct ResponseWriter<W: Write> {
+    writer: W,
+    state: WriterState,
+    groups: usize,
+    /// Group level result fields written for the current group.
+    grp_res: Option<JsonValue>,
+}
+
+impl<W: Write> ResponseWriter<W> {
+    /// Write the response header for the vector set described by `header`.
+    pub fn new(mut writer: W, header: &VectorSetHeader) -> AcvpResult<Self> {
+        let vers = json::object! {
+            acvVersion: header.version.clone()
+        };
+        let mode = match header.mode.is_empty() {
+            true => String::new(),
+            false => format!(",\"mode\":{}", json::stringify(header.mode.clone())),
+        };
+        let head = format!(
+            "[{},{{\"vsId\":{},\"algorithm\":{}{},\"revision\":{},\"isSample\":{},\"testGroups\":[",
+            vers.dump(),
+            header.vsid,
+            json::stringify(header.algorithm.clone()),
+            mode,
+            json::stringify(header.revision.clone()),
+            header.is_sample
+        );
+        writer.write_all(head.as_bytes()).map_err(AcvpError::io)?;
+        Ok(ResponseWriter {
+            writer,
+            state: WriterState::Groups,
+            groups: 0,
+            grp_res: None,
+        })
+    }
+
+    fn write(&mut self, s: &str) -> AcvpResult<()> {
+        self.writer.write_all(s.as_bytes()).map_err(AcvpError::io)
+    }
+
+    /// Start the results of test group `tgid`, ending the previous group.
+    pub fn begin_group(&mut self, tgid: u32) -> AcvpResult<()> {
+        self.end_group()?;
+        match self.state {
+            WriterState::Groups => {}
+            _ => return Err(AcvpError::invalid("The response is already finished")),
+        }
+        self.state = WriterState::Group { tgid, tests: 0 };
+        Ok(())
+    }
+
+    fn open_group(&mut self, tgid: u32, grp_res: Option<JsonValue>) -> AcvpResult<()> {
+        let mut head = json::object! {
+            tgId: tgid,
+        };
+        if let Some(grp_res) = &grp_res {
+            for (key, val) in grp_res.entries() {
+                head[key] = val.clone();
+            }
+        }
+        self.grp_res = grp_res;
+        let mut head = head.dump();
+        head.pop();
+        if self.groups > 0 {
+            self.write(",")?;
+        }
+        self.write(&head)?;
+        self.write(",\"tests\":[")?;
+        self.groups += 1;
+        Ok(())
+    }
+
+    /// Write the result of a test of the current group.
+    pub fn write_test<X: TestCase>(&mut self, test: &AcvpTest<X>) -> AcvpResult<()> {
+        let (tgid, tests) = match self.state {
+            WriterState::Group { tgid, tests } => (tgid, tests),
+            _ => return Err(AcvpError::invalid("No test group has been started")),
+        };
+        let res = test.get_result().map_err(|e| e.with_tgid(tgid))?;
+        if tests == 0 {
+            self.open_group(tgid, test.get_group_result())?;
+        } else {
+            let grp_res = test.get_group_result();
+            if grp_res.is_some() && grp_res != self.grp_res {
+                return Err(AcvpError::invalid_result(
+                    "Group result differs from the one of an earlier test",
+                )
+                .with_tcid(test.tcid)
+                .with_tgid(tgid));
+            }
+            self.write(",")?;
+        }
+        self.write(&res.dump())?;
+        self.state = WriterState::Group {
+            tgid,
+            tests: tests + 1,
+        };
+        Ok(())
+    }
+
+    /// End the current test group, if any.
+    pub fn end_group(&mut self) -> AcvpResult<()> {
+        if let WriterState::Group { tgid, tests } = self.state {
+            if tests == 0 {
+                self.open_group(tgid, None)?;
+            }
+            self.write("]}")?;
+            self.state = WriterState::Groups;
+        }
+        Ok(())
+    }
+
+    /// End the response and return the underlying writer.
+    pub fn finish(mut self) -> AcvpResult<W> {
+        self.end_group()?;
+        self.write("]}]")?;
+        self.state = WriterState::Finished;
+        self.writer.flush().map_err(AcvpError::io)?;
+        Ok(self.writer)
+    }
+}
@@ -5,88 +5,283 @@
 
 use json::JsonValue;
 
-use crate::{AcvpError, AcvpResult};
-
-const HASHES: &[&str; 11] = &[
-    "SHA-1",
-    "SHA2-224",
-    "SHA2-256",
-    "SHA2-384",
-    "SHA2-512",
-    "SHA3-224",
-    "SHA3-256",
-    "SHA3-384",
-    "SHA3-512",
-    "SHAKE-128",
-    "SHAKE-256",
-];
-
-const BLKCIPHERS: &[&str; 8] = &[
-    "ACVP-AES-CBC",
-    "ACVP-AES-CTR",
-    "ACVP-AES-ECB",
-    "ACVP-AES-GCM",
-    "ACVP-TDES-ECB",
-    "ACVP-TDES-CBC",
-    "ACVP-TDES-CTR",
-    "ACVP-AES-XTS",
-];
-
-const MACS: &[&str; 14] = &[
-    "HMAC-SHA-1",
-    "HMAC-SHA2-224",
-    "HMAC-SHA2-256",
-    "HMAC-SHA2-384",
-    "HMAC-SHA2-512",
-    "HMAC-SHA3-224",
-    "HMAC-SHA3-256",
-    "HMAC-SHA3-384",
-    "HMAC-SHA3-512",
-    "CMAC-AES",
-    "CMAC-TDES",
-    "ACVP-AES-GCM",
-    "ACVP-AES-GMAC",
-    "ACVP-AES-CCM",
-];
-
-const RNGS: &[&str; 3] = &["hashDRBG", "ctrDRBG", "hmacDRBG"];
+use crate::{
+    conditioning::ConditioningMode, dsa::DsaMode, rsa::RsaPrimitiveMode,
+    safeprimes::SafePrimesMode, AcvpError, AcvpResult,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum AcvpAlgorithm {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HashAlg {
+    SHA1,
+    SHA224,
+    SHA256,
+    SHA384,
+    SHA512,
+    SHA512_224,
+    SHA512_256,
+    SHA3_224,
+    SHA3_256,
+    SHA3_384,
+    SHA3_512,
+    SHAKE128,
+    SHAKE256,
+}
+
+impl std::str::FromStr for HashAlg {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "SHA-1" => Ok(Self::SHA1),
+            "SHA2-224" => Ok(Self::SHA224),
+            "SHA2-256" => Ok(Self::SHA256),
+            "SHA2-384" => Ok(Self::SHA384),
+            "SHA2-512" => Ok(Self::SHA512),
+            "SHA2-512/224" => Ok(Self::SHA512_224),
+            "SHA2-512/256" => Ok(Self::SHA512_256),
+            "SHA3-224" => Ok(Self::SHA3_224),
+            "SHA3-256" => Ok(Self::SHA3_256),
+            "SHA3-384" => Ok(Self::SHA3_384),
+            "SHA3-512" => Ok(Self::SHA3_512),
+            "SHAKE-128" => Ok(Self::SHAKE128),
+            "SHAKE-256" => Ok(Self::SHAKE256),
+            _ => Err(AcvpError::unsupported(format!(
+                "Hash algorithm '{}' is not valid",
+                s
+            ))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+impl HashAlg {
+    /// Digest size in bytes, 0 for the extendable output functions.
+    pub fn digest_len(&self) -> usize {
+        match self {
+            Self::SHA1 => 20,
+            Self::SHA224 | Self::SHA512_224 | Self::SHA3_224 => 28,
+            Self::SHA256 | Self::SHA512_256 | Self::SHA3_256 => 32,
+            Self::SHA384 | Self::SHA3_384 => 48,
+            Self::SHA512 | Self::SHA3_512 => 64,
+            Self::SHAKE128 | Self::SHAKE256 => 0,
+        }
+    }
+
+    pub fn is_xof(&self) -> bool {
+        matches!(self, Self::SHAKE128 | Self::SHAKE256)
+    }
+
+    pub fn is_sha3(&self) -> bool {
+        matches!(
+            self,
+            Self::SHA3_224
+                | Self::SHA3_256
+                | Self::SHA3_384
+                | Self::SHA3_512
+                | Self::SHAKE128
+                | Self::SHAKE256
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlockCipherAlg {
+    AES,
+    TDES,
+}
+
+impl std::str::FromStr for BlockCipherAlg {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "AES" => Ok(Self::AES),
+            "TDES" => Ok(Self::TDES),
+            _ => Err(AcvpError::unsupported(format!(
+                "Block cipher '{}' is not valid",
+                s
+            ))),
+        }
+    }
+
+    type Err = AcvpError;
+}
+
+/// Confidentiality-only block cipher modes. `CFB64` is the full block CFB
+/// mode of TDES, `CFB128` the one of AES. CFB1 values are bit strings
+/// aligned to the most significant bit of the first byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CipherMode {
+    ECB,
+    CBC,
+    CTR,
+    XTS,
+    OFB,
+    CFB1,
+    CFB8,
+    CFB64,
+    CFB128,
+}
+
+/// Authenticated block cipher modes, handled by `msgauth::MsgAuth`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AeadMode {
+    GCM,
+    CCM,
+    GMAC,
+    XPN,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MacAlg {
+    HMAC(HashAlg),
+    CMAC(BlockCipherAlg),
+    AEAD(BlockCipherAlg, AeadMode),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrbgType {
     Hash,
-    MsgAuth,
-    BlockCipher,
-    Rng,
+    Hmac,
+    Ctr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AsconHashAlg {
+    Hash256,
+    XOF128,
+    CXOF128,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IfcAlg {
+    KasSsc,
+    Kts,
+}
+
+/// Parsed ACVP algorithm of a vector set.
+///
+/// Families whose test cases depend on the vector set `mode` (DSA, safe
+/// primes, RSA and the conditioning components) carry the parsed mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AcvpAlgorithm {
+    Hash(HashAlg),
+    MsgAuth(MacAlg),
+    BlockCipher(BlockCipherAlg, CipherMode),
+    Rng(DrbgType),
+    AsconAead,
+    AsconHash(AsconHashAlg),
+    Dsa(DsaMode),
+    SafePrimes(SafePrimesMode),
+    Ifc(IfcAlg),
+    Rsa(RsaPrimitiveMode),
+    Conditioning(ConditioningMode),
+    /// Algorithm handled by a `TestCase` registered at runtime, see
+    /// `registry::register`.
+    Custom(u32),
     Nil,
 }
 
 impl AcvpAlgorithm {
-    pub fn alg_type(alg: &str) -> AcvpResult<Self> {
-        if str_lookup(alg, HASHES) {
-            return Ok(Self::Hash);
+    /// Classify the `algorithm` and `mode` of a vector set. `mode` is only
+    /// consulted for the families which need it and may be empty otherwise.
+    pub fn alg_type(alg: &str, mode: &str) -> AcvpResult<Self> {
+        Self::alg_type_revision(alg, mode, "")
+    }
+
+    /// Classify a vector set as `alg_type` does, custom algorithms registered
+    /// for a specific revision are only matched here.
+    pub fn alg_type_revision(alg: &str, mode: &str, revision: &str) -> AcvpResult<Self> {
+        if let Some(custom) = crate::registry::lookup(alg, mode, revision) {
+            return Ok(custom);
+        }
+        if let Ok(hash) = alg.parse::<HashAlg>() {
+            return Ok(Self::Hash(hash));
         }
-        if str_lookup(alg, MACS) {
-            return Ok(Self::MsgAuth);
+        if let Some(hash) = alg.strip_prefix("HMAC-") {
+            return Ok(Self::MsgAuth(MacAlg::HMAC(hash.parse()?)));
         }
-        if str_lookup(alg, BLKCIPHERS) {
-            return Ok(Self::BlockCipher);
+        if let Some(cipher) = alg.strip_prefix("CMAC-") {
+            return Ok(Self::MsgAuth(MacAlg::CMAC(cipher.parse()?)));
         }
-        if str_lookup(alg, RNGS) {
-            return Ok(Self::Rng);
+        if let Some(name) = alg.strip_prefix("ACVP-") {
+            return Self::cipher_type(alg, name);
         }
-        Err(AcvpError {
-            code: -libc::EINVAL,
-            message: format!("Uknown type for algorithm '{}'", alg),
-        })
+
+        let alg_type = match alg {
+            "hashDRBG" => Self::Rng(DrbgType::Hash),
+            "hmacDRBG" => Self::Rng(DrbgType::Hmac),
+            "ctrDRBG" => Self::Rng(DrbgType::Ctr),
+            "Ascon-AEAD128" => Self::AsconAead,
+            "Ascon-Hash256" => Self::AsconHash(AsconHashAlg::Hash256),
+            "Ascon-XOF128" => Self::AsconHash(AsconHashAlg::XOF128),
+            "Ascon-CXOF128" => Self::AsconHash(AsconHashAlg::CXOF128),
+            "DSA" => Self::Dsa(mode.parse()?),
+            "safePrimes" => Self::SafePrimes(mode.parse()?),
+            "KAS-IFC-SSC" => Self::Ifc(IfcAlg::KasSsc),
+            "KTS-IFC" => Self::Ifc(IfcAlg::Kts),
+            "RSA" => Self::Rsa(mode.parse()?),
+            "ConditioningComponent" => Self::Conditioning(mode.parse()?),
+            _ => {
+                return Err(AcvpError::unsupported(format!(
+                    "Uknown type for algorithm '{}'",
+                    alg
+                )));
+            }
+        };
+        Ok(alg_type)
+    }
+
+    fn cipher_type(alg: &str, name: &str) -> AcvpResult<Self> {
+        let (cipher, mode) = match name.split_once('-') {
+            Some((cipher, mode)) => (cipher.parse::<BlockCipherAlg>()?, mode),
+            None => ("".parse::<BlockCipherAlg>()?, ""),
+        };
+        let alg_type = match (cipher, mode) {
+            (_, "ECB") => Self::BlockCipher(cipher, CipherMode::ECB),
+            (_, "CBC") => Self::BlockCipher(cipher, CipherMode::CBC),
+            (_, "CTR") => Self::BlockCipher(cipher, CipherMode::CTR),
+            (BlockCipherAlg::AES, "XTS") => Self::BlockCipher(cipher, CipherMode::XTS),
+            (_, "OFB") => Self::BlockCipher(cipher, CipherMode::OFB),
+            (_, "CFB1") => Self::BlockCipher(cipher, CipherMode::CFB1),
+            (_, "CFB8") => Self::BlockCipher(cipher, CipherMode::CFB8),
+            (BlockCipherAlg::TDES, "CFB64") => Self::BlockCipher(cipher, CipherMode::CFB64),
+            (BlockCipherAlg::AES, "CFB128") => Self::BlockCipher(cipher, CipherMode::CFB128),
+            (BlockCipherAlg::AES, "GCM") => Self::MsgAuth(MacAlg::AEAD(cipher, AeadMode::GCM)),
+            (BlockCipherAlg::AES, "CCM") => Self::MsgAuth(MacAlg::AEAD(cipher, AeadMode::CCM)),
+            (BlockCipherAlg::AES, "GMAC") => Self::MsgAuth(MacAlg::AEAD(cipher, AeadMode::GMAC)),
+            (BlockCipherAlg::AES, "XPN") => Self::MsgAuth(MacAlg::AEAD(cipher, AeadMode::XPN)),
+            _ => {
+                return Err(AcvpError::unsupported(format!(
+                    "Uknown type for algorithm '{}'",
+                    alg
+                )));
+            }
+        };
+        Ok(alg_type)
+    }
+
+    pub fn is_aead(&self) -> bool {
+        matches!(self, Self::MsgAuth(MacAlg::AEAD(..)))
     }
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TestType {
     AFT,
     CTR,
     MCT,
     LDT,
+    GDT,
+    VAL,
+    VOT,
     Nil,
 }
 
@@ -97,11 +292,11 @@ impl TestType {
             "CTR" => TestType::CTR,
             "MCT" => TestType::MCT,
             "LDT" => TestType::LDT,
+            "GDT" => TestType::GDT,
+            "VAL" => TestType::VAL,
+            "VOT" => TestType::VOT,
             _ => {
-                return Err(AcvpError {
-                    code: -libc::EINVAL,
-                    message: format!("Invalid test type '{}'", ttype),
-                });
+                return Err(AcvpError::invalid(format!("Invalid test type '{}'", ttype)));
             }
         };
         Ok(test_type)
@@ -109,6 +304,7 @@ impl TestType {
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Encrypt,
     Decrypt,
@@ -125,10 +321,10 @@ impl Direction {
             "gen" => Direction::Generate,
             "ver" => Direction::Verify,
             _ => {
-                return Err(AcvpError {
-                    code: -libc::EINVAL,
-                    message: format!("Invalid direction '{}'", direction),
-                });
+                return Err(AcvpError::invalid(format!(
+                    "Invalid direction '{}'",
+                    direction
+                )));
             }
         };
         Ok(dir)
@@ -136,6 +332,7 @@ impl Direction {
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IVMode {
     Internal,
     External,
@@ -148,31 +345,18 @@ impl IVMode {
             "internal" => IVMode::Internal,
             "external" => IVMode::External,
             _ => {
-                return Err(AcvpError {
-                    code: -libc::EINVAL,
-                    message: format!("Invalid IVGenMode '{}'", mode),
-                });
+                return Err(AcvpError::invalid(format!("Invalid IVGenMode '{}'", mode)));
             }
         };
         Ok(ivmode)
     }
 }
 
-fn str_lookup(key: &str, arr: &[&str]) -> bool {
-    if let Some(_str) = arr.iter().find(|&s| *s == key) {
-        return true;
-    }
-    false
-}
-
 pub fn get_acvp_str(key: &str, json: &JsonValue) -> AcvpResult<String> {
     let value = match json[key].as_str() {
         Some(val) => val,
         None => {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: format!("Failed to obtain str value associated with key '{}'", key,),
-            });
+            return Err(AcvpError::missing_field(key, "str"));
         }
     };
     Ok(value.to_string())
@@ -182,10 +366,17 @@ pub fn get_acvp_u32(key: &str, json: &JsonValue) -> AcvpResult<u32> {
     let value = match json[key].as_u32() {
         Some(val) => val,
         None => {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: format!("Failed to obtain u32 value associated with key '{}'", key,),
-            });
+            return Err(AcvpError::missing_field(key, "u32"));
+        }
+    };
+    Ok(value)
+}
+
+pub fn get_acvp_u64(key: &str, json: &JsonValue) -> AcvpResult<u64> {
+    let value = match json[key].as_u64() {
+        Some(val) => val,
+        None => {
+            return Err(AcvpError::missing_field(key, "u64"));
         }
     };
     Ok(value)
@@ -195,13 +386,7 @@ pub fn get_acvp_bool(key: &str, json: &JsonValue) -> AcvpResult<bool> {
     let value = match json[key].as_bool() {
         Some(val) => val,
         None => {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: format!(
-                    "Failed to obtain boolean value associated with key '{}'",
-                    key,
-                ),
-            });
+            return Err(AcvpError::missing_field(key, "bool"));
         }
     };
     Ok(value)
@@ -210,39 +395,73 @@ pub fn get_acvp_bool(key: &str, json: &JsonValue) -> AcvpResult<bool> {
 pub(crate) fn hex2bin(hex: &str) -> AcvpResult<Vec<u8>> {
     let bin = match hex::decode(hex) {
         Ok(bin) => bin,
-        Err(_e) => {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: format!("Invalid hex string '{}'", hex),
-            });
+        Err(e) => {
+            return Err(AcvpError::bad_hex(hex, e));
         }
     };
     Ok(bin)
 }
 
+/// Decode a hex encoded big integer into its big-endian byte representation.
+///
+/// Unlike `hex2bin`, odd length strings are accepted and leading zero bytes
+/// are stripped, so values such as RSA moduli and DSA domain parameters
+/// always come back in their minimal form.
+pub(crate) fn hex2mpi(hex: &str) -> AcvpResult<Vec<u8>> {
+    let mut bin = if hex.len() % 2 == 1 {
+        hex2bin(&format!("0{}", hex))?
+    } else {
+        hex2bin(hex)?
+    };
+    let zeros = bin.iter().take_while(|&&b| b == 0).count();
+    bin.drain(..zeros);
+    Ok(bin)
+}
+
+/// Encode a big-endian big integer as an upper case hex string without
+/// leading zero bytes.
+pub(crate) fn mpi2hex(mpi: &[u8]) -> String {
+    let zeros = mpi.iter().take_while(|&&b| b == 0).count();
+    hex::encode(&mpi[zeros..]).to_ascii_uppercase()
+}
+
+pub fn get_acvp_mpi(key: &str, json: &JsonValue) -> AcvpResult<Vec<u8>> {
+    let mpihex = get_acvp_str(key, json)?;
+    hex2mpi(&mpihex).map_err(|e| e.at(key))
+}
+
 pub fn get_algorithm_type(vector: &str) -> AcvpResult<AcvpAlgorithm> {
     let vec = match json::parse(vector) {
         Ok(vec) => vec,
-        Err(_e) => {
-            return Err(AcvpError {
-                code: -libc::EINVAL,
-                message: "Invalid input vector, failed to get algorithm type".to_string(),
-            });
+        Err(e) => {
+            return Err(AcvpError::parse_json(
+                "Invalid input vector, failed to get algorithm type",
+                e,
+            ));
         }
     };
+    get_algorithm_type_json(&vec)
+}
 
+/// Classify an already parsed vector set, see `get_algorithm_type`.
+pub fn get_algorithm_type_json(vec: &JsonValue) -> AcvpResult<AcvpAlgorithm> {
     let mut alg_type = AcvpAlgorithm::Nil;
     for v in vec.members() {
         if v.has_key("algorithm") {
             let alg = get_acvp_str("algorithm", v)?;
-            alg_type = AcvpAlgorithm::alg_type(&alg)?;
+            let mut mode = "".to_string();
+            if v.has_key("mode") {
+                mode = get_acvp_str("mode", v)?;
+            }
+            let mut revision = "".to_string();
+            if v.has_key("revision") {
+                revision = get_acvp_str("revision", v)?;
+            }
+            alg_type = AcvpAlgorithm::alg_type_revision(&alg, &mode, &revision)?;
         }
     }
     if alg_type == AcvpAlgorithm::Nil {
-        return Err(AcvpError {
-            code: -libc::EINVAL,
-            message: "No 'algorithm' key present in input vector".to_string(),
-        });
+        return Err(AcvpError::missing_field("algorithm", "str"));
     }
 
     Ok(alg_type)
@@ -0,0 +1,160 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Completeness and shape checks of the results of a request before it is
+//! submitted.
+//!
+//! Unlike `AcvpRequest::get_result`, which stops at the first test without
+//! a result, `AcvpRequest::validate` goes through every test and reports all
+//! unanswered tests and malformed results at once. The result checks
+//! themselves are implemented per algorithm by `TestCase::validate_result`.
+
+use std::fmt;
+
+use json::JsonValue;
+
+use crate::{
+    parser::{AcvpRequest, TestCase},
+    AcvpError,
+};
+
+/// Number of iterations reported by a Monte Carlo test.
+pub const MCT_ITERATIONS: usize = 100;
+
+/// Number of iterations reported by a TDES Monte Carlo test.
+pub const TDES_MCT_ITERATIONS: usize = 400;
+
+/// A problem found in the result of a test case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub tgid: u32,
+    pub tcid: u32,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "tgId {} tcId {}: {}", self.tgid, self.tcid, self.message)
+    }
+}
+
+/// Outcome of `AcvpRequest::validate`. `unanswered` lists the `(tgId, tcId)`
+/// of every test without a result, in request order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub vsid: u32,
+    pub unanswered: Vec<(u32, u32)>,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.unanswered.is_empty() && self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "vsId {}: {} unanswered tests, {} invalid results",
+            self.vsid,
+            self.unanswered.len(),
+            self.issues.len()
+        )?;
+        for (tgid, tcid) in &self.unanswered {
+            write!(f, "\ntgId {} tcId {}: no result", tgid, tcid)?;
+        }
+        for issue in &self.issues {
+            write!(f, "\n{}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+fn hex_len_issue(val: &JsonValue, expected: usize) -> Option<String> {
+    match val.as_str() {
+        Some(hex) if hex.len() == 2 * expected => None,
+        Some(hex) => Some(format!("is {} bytes, expected {}", hex.len() / 2, expected)),
+        None => Some("is missing".to_string()),
+    }
+}
+
+/// Check that the hex string `field` of `res` is `expected` bytes long, a
+/// missing field is reported as well.
+pub(crate) fn check_hex_len(res: &JsonValue, field: &str, expected: usize, out: &mut Vec<String>) {
+    if let Some(issue) = hex_len_issue(&res[field], expected) {
+        out.push(format!("{} {}", field, issue));
+    }
+}
+
+/// Check the hex string `field` of every Monte Carlo test iteration as
+/// `check_hex_len` does.
+pub(crate) fn check_mct_hex_len(
+    entries: &[&JsonValue],
+    field: &str,
+    expected: usize,
+    out: &mut Vec<String>,
+) {
+    for (i, entry) in entries.iter().enumerate() {
+        if let Some(issue) = hex_len_issue(&entry[field], expected) {
+            out.push(format!("resultsArray[{}].{} {}", i, field, issue));
+        }
+    }
+}
+
+/// Check that `res` holds the `resultsArray` of a Monte Carlo test with
+/// `iterations` entries, and return the entries found.
+pub(crate) fn check_mct_results<'a>(
+    res: &'a JsonValue,
+    iterations: usize,
+    out: &mut Vec<String>,
+) -> Vec<&'a JsonValue> {
+    let results = &res["resultsArray"];
+    if !results.is_array() {
+        out.push("resultsArray is missing".to_string());
+        return Vec::new();
+    }
+    if results.len() != iterations {
+        out.push(format!(
+            "resultsArray has {} entries, expected {}",
+            results.len(),
+            iterations
+        ));
+    }
+    results.members().collect()
+}
+
+impl<T: TestCase> AcvpRequest<T> {
+    /// Check that every test has a result and that the results are well
+    /// formed. Results attached from a response count as answers.
+    pub fn validate(&self) -> ValidationReport {
+        let mut unanswered = Vec::new();
+        let mut issues = Vec::new();
+        for tg in &self.testgroups {
+            for test in &tg.tests {
+                let messages = match test.get_result() {
+                    Ok(res) => test.validate_result(&res),
+                    Err(AcvpError::ResultNotSet { .. }) => {
+                        unanswered.push((tg.tgid, test.tcid));
+                        continue;
+                    }
+                    Err(e) => vec![e.message()],
+                };
+                issues.extend(messages.into_iter().map(|message| ValidationIssue {
+                    tgid: tg.tgid,
+                    tcid: test.tcid,
+                    message,
+                }));
+            }
+        }
+
+        ValidationReport {
+            vsid: self.vsid,
+            unanswered,
+            issues,
+        }
+    }
+}
@@ -0,0 +1,331 @@
+/*
+ * Copyright 2021-2022 Juniper Networks, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Comparison of results against the expected answers of sample vector sets.
+//!
+//! The expected answers are loaded as an `AcvpResponse`, either from the
+//! `expectedResults.json` file or from the `internalProjection.json` file,
+//! which carries the expected answers next to the test inputs. Fields of the
+//! result which are missing from the expected answer, and expected fields
+//! which are missing from the result, are mismatches. Expected fields which
+//! are inputs of the test are ignored, so internal projections can be used.
+
+use std::fmt;
+
+use json::JsonValue;
+
+use crate::{
+    parser::{AcvpRequest, TestCase},
+    response::AcvpResponse,
+    AcvpError, AcvpResult,
+};
+
+/// Hex strings are shown this many characters either side of the first
+/// difference.
+const DIFF_WINDOW: usize = 32;
+
+/// Hex fields holding big integers, which are reported without leading
+/// zeros but may be expected with them.
+const MPI_FIELDS: &[&str] = &[
+    "p", "q", "g", "x", "y", "r", "s", "n", "e", "d", "dmp1", "dmq1", "iqmp",
+];
+
+/// A result field which does not match the expected answer. `expected` is
+/// `None` if the expected answer lacks the field, `actual` if the result
+/// lacks it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldMismatch {
+    pub field: String,
+    pub expected: Option<JsonValue>,
+    pub actual: Option<JsonValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail(Vec<FieldMismatch>),
+    /// No result was set on the test.
+    NoResult,
+    /// The expected answers have no entry for the test.
+    NoExpected,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestVerdict {
+    pub tgid: u32,
+    pub tcid: u32,
+    pub verdict: Verdict,
+}
+
+impl TestVerdict {
+    pub fn passed(&self) -> bool {
+        self.verdict == Verdict::Pass
+    }
+}
+
+/// Verdicts of all tests of a request, in request order. Mismatching group
+/// level fields are reported against every test of the group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verification {
+    pub vsid: u32,
+    pub tests: Vec<TestVerdict>,
+}
+
+impl Verification {
+    pub fn passed(&self) -> bool {
+        self.tests.iter().all(|t| t.passed())
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &TestVerdict> {
+        self.tests.iter().filter(|t| !t.passed())
+    }
+}
+
+fn is_hex(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn values_match(key: &str, expected: &JsonValue, actual: &JsonValue) -> bool {
+    match (expected.as_str(), actual.as_str()) {
+        (Some(e), Some(a)) if is_hex(e) && is_hex(a) && MPI_FIELDS.contains(&key) => e
+            .trim_start_matches('0')
+            .eq_ignore_ascii_case(a.trim_start_matches('0')),
+        (Some(e), Some(a)) if is_hex(e) && is_hex(a) => e.eq_ignore_ascii_case(a),
+        _ => expected == actual,
+    }
+}
+
+/// Compare `actual` with `expected` field by field. `inputs` is the input
+/// JSON of the test or group, expected fields which are inputs need not be
+/// part of the result.
+fn compare(
+    path: &str,
+    key: &str,
+    expected: &JsonValue,
+    actual: &JsonValue,
+    inputs: &JsonValue,
+    out: &mut Vec<FieldMismatch>,
+) {
+    let field = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match actual {
+        JsonValue::Object(_) if expected.is_object() => {
+            for (key, val) in actual.entries() {
+                if expected.has_key(key) {
+                    compare(&field(key), key, &expected[key], val, &inputs[key], out);
+                } else {
+                    out.push(FieldMismatch {
+                        field: field(key),
+                        expected: None,
+                        actual: Some(val.clone()),
+                    });
+                }
+            }
+            for (key, val) in expected.entries() {
+                if !actual.has_key(key) && !inputs.has_key(key) {
+                    out.push(FieldMismatch {
+                        field: field(key),
+                        expected: Some(val.clone()),
+                        actual: None,
+                    });
+                }
+            }
+        }
+        JsonValue::Array(arr) if expected.is_array() && expected.len() == arr.len() => {
+            for (i, val) in arr.iter().enumerate() {
+                let path = format!("{}[{}]", path, i);
+                compare(&path, key, &expected[i], val, &inputs[i], out);
+            }
+        }
+        _ => {
+            if !values_match(key, expected, actual) {
+                out.push(FieldMismatch {
+                    field: path.to_string(),
+                    expected: Some(expected.clone()),
+                    actual: Some(actual.clone()),
+                });
+            }
+        }
+    }
+}
+
+/// Show the window of two hex strings around their first difference, with
+/// a marker under the differing character.
+fn hex_diff(f: &mut fmt::Formatter, expected: &str, actual: &str) -> fmt::Result {
+    let pos = expected
+        .bytes()
+        .zip(actual.bytes())
+        .position(|(e, a)| !e.eq_ignore_ascii_case(&a))
+        .unwrap_or_else(|| expected.len().min(actual.len()));
+    let start = pos.saturating_sub(DIFF_WINDOW);
+    let window = |s: &str| {
+        let end = s.len().min(pos + DIFF_WINDOW);
+        let prefix = if start > 0 { "..." } else { "" };
+        let suffix = if end < s.len() { "..." } else { "" };
+        format!("{}{}{}", prefix, &s[start.min(s.len())..end], suffix)
+    };
+    let indent = pos - start + if start > 0 { 3 } else { 0 };
+    writeln!(f, "    expected: {}", window(expected))?;
+    writeln!(f, "    actual:   {}", window(actual))?;
+    write!(
+        f,
+        "              {}^ first difference at character {} (expected {} bytes, got {})",
+        " ".repeat(indent),
+        pos,
+        expected.len() / 2,
+        actual.len() / 2
+    )
+}
+
+impl fmt::Display for FieldMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "  {}:", self.field)?;
+        match (&self.expected, &self.actual) {
+            (None, Some(actual)) => write!(f, "    not in the expected results, got {}", actual),
+            (Some(expected), None) => write!(f, "    missing, expected {}", expected),
+            (Some(expected), Some(actual))
+                if expected.as_str().is_some_and(is_hex) && actual.as_str().is_some_and(is_hex) =>
+            {
+                hex_diff(
+                    f,
+                    expected.as_str().unwrap_or_default(),
+                    actual.as_str().unwrap_or_default(),
+                )
+            }
+            (expected, actual) => {
+                writeln!(
+                    f,
+                    "    expected: {}",
+                    expected.clone().unwrap_or(JsonValue::Null)
+                )?;
+                write!(
+                    f,
+                    "    actual:   {}",
+                    actual.clone().unwrap_or(JsonValue::Null)
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for TestVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "tgId {} tcId {}: ", self.tgid, self.tcid)?;
+        match &self.verdict {
+            Verdict::Pass => write!(f, "pass"),
+            Verdict::NoResult => write!(f, "no result"),
+            Verdict::NoExpected => write!(f, "no expected result"),
+            Verdict::Fail(mismatches) => {
+                write!(f, "FAIL")?;
+                for m in mismatches {
+                    write!(f, "\n{}", m)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let failed = self.failures().count();
+        write!(
+            f,
+            "vsId {}: {} of {} tests passed",
+            self.vsid,
+            self.tests.len() - failed,
+            self.tests.len()
+        )?;
+        for t in self.failures() {
+            write!(f, "\n{}", t)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: TestCase> AcvpRequest<T> {
+    /// Compare the results of all tests with the expected answers.
+    pub fn verify(&self, expected: &AcvpResponse) -> AcvpResult<Verification> {
+        if expected.vsid != self.vsid {
+            return Err(AcvpError::invalid(format!(
+                "Expected results are for vsId {}, the request is vsId {}",
+                expected.vsid, self.vsid
+            ))
+            .with_vsid(self.vsid));
+        }
+
+        let mut tests = Vec::new();
+        for tg in &self.testgroups {
+            let egroup = expected.find_group(tg.tgid);
+            let mut grp_mismatches = Vec::new();
+            let grp_res = tg
+                .tests
+                .iter()
+                .find_map(|t| t.get_group_result())
+                .or_else(|| tg.stored_result.clone());
+            if let Some(egroup) = egroup {
+                if grp_res.is_some() || egroup.group_result.is_some() {
+                    let eres = egroup
+                        .group_result
+                        .clone()
+                        .unwrap_or_else(JsonValue::new_object);
+                    let grp_res = grp_res.unwrap_or_else(JsonValue::new_object);
+                    let inputs = &tg.testgroup_json;
+                    compare("", "", &eres, &grp_res, inputs, &mut grp_mismatches);
+                }
+            }
+
+            for test in &tg.tests {
+                let actual = match test.get_result() {
+                    Ok(actual) => actual,
+                    Err(AcvpError::ResultNotSet { .. }) => {
+                        tests.push(TestVerdict {
+                            tgid: tg.tgid,
+                            tcid: test.tcid,
+                            verdict: Verdict::NoResult,
+                        });
+                        continue;
+                    }
+                    Err(e) => return Err(e.with_vsid(self.vsid).with_tgid(tg.tgid)),
+                };
+                let etest = match egroup.and_then(|g| g.find_test(test.tcid)) {
+                    Some(etest) => etest,
+                    None => {
+                        tests.push(TestVerdict {
+                            tgid: tg.tgid,
+                            tcid: test.tcid,
+                            verdict: Verdict::NoExpected,
+                        });
+                        continue;
+                    }
+                };
+
+                let mut mismatches = grp_mismatches.clone();
+                let inputs = &test.test_json;
+                compare("", "", &etest.result, &actual, inputs, &mut mismatches);
+                let verdict = if mismatches.is_empty() {
+                    Verdict::Pass
+                } else {
+                    Verdict::Fail(mismatches)
+                };
+                tests.push(TestVerdict {
+                    tgid: tg.tgid,
+                    tcid: test.tcid,
+                    verdict,
+                });
+            }
+        }
+
+        Ok(Verification {
+            vsid: self.vsid,
+            tests,
+        })
+    }
+}